@@ -0,0 +1,207 @@
+//! Archives bundling a project with its media, to move it to another computer
+
+use bt_save::SaveFile;
+use std::{
+    fs::File,
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+};
+use tar::Header;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("could not read or write the save file")]
+    Save(#[from] bt_save::Error),
+    #[error("an I/O error occured")]
+    Io(#[from] io::Error),
+    #[error("{0} is not a file")]
+    NotAFile(&'static str),
+    #[error("{0:?} already contains a project")]
+    Exists(PathBuf),
+}
+
+/// Name of the project inside of the archive
+pub const SAVE_NAME: &str = "save.bt";
+
+/// Writes the project at `input` and all of its media to the archive `output`
+pub fn archive_save<I: AsRef<Path>, O: AsRef<Path>>(input: I, output: O) -> Result<(), Error> {
+    let mut save = bt_save::load(input)?;
+    let mut tar = tar::Builder::new(File::create(output)?);
+
+    for clip in &mut save.clips {
+        let mut path = PathBuf::from("clips");
+        path.push(&clip.title);
+
+        let mut new_music = path.clone();
+        new_music.push("music");
+        new_music.push(
+            clip.music_path
+                .file_name()
+                .ok_or(Error::NotAFile("music"))?,
+        );
+
+        tar.append_path_with_name(&clip.music_path, &new_music)?;
+        clip.music_path = new_music;
+
+        if let Some(image) = &mut clip.image_path {
+            let mut new_image = path.clone();
+            new_image.push("image");
+            new_image.push(image.file_name().ok_or(Error::NotAFile("image"))?);
+
+            tar.append_path_with_name(&image, &new_image)?;
+            *image = new_image;
+        }
+
+        if let Some(video) = &mut clip.video_path {
+            let mut new_video = path;
+            new_video.push("video");
+            new_video.push(video.file_name().ok_or(Error::NotAFile("video"))?);
+
+            tar.append_path_with_name(&video, &new_video)?;
+            *video = new_video;
+        }
+    }
+
+    if let Some(countdown) = &mut save.settings.countdown {
+        let mut countdown_path = PathBuf::from("countdown");
+        countdown_path.push(countdown.file_name().ok_or(Error::NotAFile("countdown"))?);
+
+        tar.append_path_with_name(&countdown, &countdown_path)?;
+        *countdown = countdown_path;
+    }
+
+    let (len, save_file) = save.data()?;
+    let mut header = Header::new_gnu();
+    header.set_cksum();
+    header.set_size(len as u64);
+    header.set_mode(0o644);
+
+    tar.append_data(&mut header, SAVE_NAME, save_file)?;
+    tar.finish()?;
+
+    Ok(())
+}
+
+/// Unpacks the archive `input` in the folder `output`, returning the path of the project, whose
+/// media now point to the unpacked files. A project already in `output` is not overwritten.
+pub fn load_archive<I: AsRef<Path>, O: AsRef<Path>>(input: I, output: O) -> Result<PathBuf, Error> {
+    let mut path = output.as_ref().to_owned();
+    path.push(SAVE_NAME);
+    if path.exists() {
+        return Err(Error::Exists(output.as_ref().to_owned()));
+    }
+
+    let mut tar = tar::Archive::new(BufReader::new(File::open(input)?));
+    tar.unpack(output.as_ref())?;
+
+    let mut save_file = SaveFile::load(&path)?;
+    let base_path = output.as_ref().canonicalize()?;
+    for clip in &mut save_file.clips {
+        let mut music_path = base_path.clone();
+        music_path.push(&clip.music_path);
+        clip.music_path = music_path;
+
+        if let Some(image) = &mut clip.image_path {
+            let mut image_path = base_path.clone();
+            image_path.push(&image);
+            *image = image_path;
+        }
+
+        if let Some(video) = &mut clip.video_path {
+            let mut video_path = base_path.clone();
+            video_path.push(&video);
+            *video = video_path;
+        }
+    }
+
+    if let Some(countdown) = &mut save_file.settings.countdown {
+        let mut countdown_path = base_path.clone();
+        countdown_path.push(&countdown);
+        *countdown = countdown_path;
+    }
+
+    save_file.store(&path)?;
+
+    Ok(path)
+}
+
+/// Folder an archive is unpacked to when none is given: next to it, named after it, with a
+/// number when a file or folder already has this name
+pub fn default_folder(archive: &Path) -> PathBuf {
+    let folder = archive.with_extension("");
+    if !folder.exists() {
+        return folder;
+    }
+
+    let name = folder.file_name().unwrap_or_default().to_owned();
+    (2..)
+        .map(|number| {
+            let mut name = name.clone();
+            name.push(format!(" ({})", number));
+            folder.with_file_name(name)
+        })
+        .find(|folder| !folder.exists())
+        .expect("no free folder name")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bt_save::{ClipSave, Settings};
+    use std::{fs, time::Duration};
+
+    #[test]
+    fn archive_and_load() {
+        let dir = std::env::temp_dir().join(format!("bt-archive-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let music = dir.join("song.mp3");
+        fs::write(&music, b"not really music").unwrap();
+
+        let save = SaveFile {
+            clips: vec![ClipSave {
+                title: "A song".into(),
+                image_path: None,
+                music_path: music,
+                offset: Duration::from_secs(3),
+                video_path: None,
+                reveal_effect: None,
+                challenge: None,
+                music_hash: None,
+                artist: None,
+                album: None,
+            }],
+            timeline: vec![Some("A song".into()), None],
+            settings: Settings {
+                duration: 20,
+                countdown: None,
+                reveal_effect: Default::default(),
+                challenge: Default::default(),
+            },
+        };
+        let project = dir.join("project.bt");
+        save.store(&project).unwrap();
+
+        let archive = dir.join("project.bta");
+        archive_save(&project, &archive).unwrap();
+        let folder = default_folder(&archive);
+        assert_eq!(folder, dir.join("project"));
+        let loaded = load_archive(&archive, &folder).unwrap();
+        assert_eq!(loaded, folder.join(SAVE_NAME));
+
+        // Opening it again unpacks it in another folder, or fails when the folder is forced
+        assert_eq!(default_folder(&archive), dir.join("project (2)"));
+        assert!(matches!(
+            load_archive(&archive, &folder),
+            Err(Error::Exists(_))
+        ));
+
+        let loaded = SaveFile::load(&loaded).unwrap();
+        let clip = &loaded.clips[0];
+        assert!(clip.music_path.starts_with(folder.canonicalize().unwrap()));
+        assert_eq!(fs::read(&clip.music_path).unwrap(), b"not really music");
+        assert_eq!(clip.offset, Duration::from_secs(3));
+        assert_eq!(loaded.timeline, save.timeline);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
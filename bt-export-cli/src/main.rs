@@ -1,11 +1,14 @@
-use bt_export::{clip_duration_command, ffmpeg_command};
+use bt_export::{
+    audio_ffmpeg_command, ffmpeg_command, media_info, AudioCue, AudioFormat, Item,
+    DEFAULT_TICK_DURATION,
+};
 use bt_save::SaveFile;
 use color_eyre::eyre::{self, eyre, WrapErr};
 use indicatif::ProgressStyle;
 use std::{
     collections::HashMap,
     io::{BufRead, BufReader},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 use structopt::StructOpt;
 
@@ -13,10 +16,22 @@ use structopt::StructOpt;
 struct Args {
     #[structopt(long = "save", short = "-i")]
     save_file: PathBuf,
-    #[structopt(long = "output", short = "-o", default_value = "output.mp4")]
-    output: PathBuf,
+    /// Defaults to output.mp4, or output.mp3 when exporting audio only
+    #[structopt(long = "output", short = "-o")]
+    output: Option<PathBuf>,
     #[structopt(long = "threads", short = "-t")]
     threads: Option<u64>,
+    /// Render only the audio track (mp3, ogg, flac or wav depending on the output)
+    #[structopt(long = "audio-only")]
+    audio_only: bool,
+    /// Use a generated tick instead of the countdown audio in audio only mode
+    #[structopt(long = "tick")]
+    tick: bool,
+}
+
+fn countdown_duration(countdown: &Path) -> color_eyre::Result<u32> {
+    let info = media_info::probe(countdown).wrap_err("could not fetch countdown length")?;
+    Ok(info.duration.as_secs() as u32)
 }
 
 fn main() -> color_eyre::Result<()> {
@@ -24,20 +39,22 @@ fn main() -> color_eyre::Result<()> {
     let args = Args::from_args();
 
     let save_file = SaveFile::load(args.save_file).wrap_err("could not open save file")?;
-    let countdown = &save_file
-        .settings
-        .countdown
-        .ok_or(eyre!("save file has no coutdown"))?;
+    let output = match args.output {
+        Some(output) => output,
+        None if args.audio_only => "output.mp3".into(),
+        None => "output.mp4".into(),
+    };
 
-    let mut cmd = clip_duration_command(countdown);
-    let countdown_command = cmd.output().wrap_err("could not fetch countdown length")?;
+    let countdown = match &save_file.settings.countdown {
+        Some(countdown) if !(args.audio_only && args.tick) => Some(countdown),
+        _ if args.audio_only => None,
+        _ => eyre::bail!("save file has no coutdown"),
+    };
 
-    let countdown_duration: f32 = String::from_utf8(countdown_command.stdout)
-        .expect("ffprobe should give utf8")
-        .trim_end()
-        .parse()
-        .expect("ffprobe should give a duration");
-    let countdown_duration = countdown_duration as u32;
+    let countdown_duration = match countdown {
+        Some(countdown) => countdown_duration(countdown)?,
+        None => DEFAULT_TICK_DURATION,
+    };
     if countdown_duration > save_file.settings.duration {
         eyre::bail!("countdown is longer than the clip length");
     }
@@ -56,23 +73,47 @@ fn main() -> color_eyre::Result<()> {
             clips
                 .get(name)
                 .ok_or_else(|| eyre!("Clip '{}' does not exist", name))
-                .map(|clip| {
-                    (
-                        clip.offset,
-                        clip.music_path.clone(),
-                        clip.image_path.clone(),
-                    )
+                .map(|clip| Item {
+                    offset: clip.offset,
+                    music: clip.music_path.clone(),
+                    image: clip.image_path.clone(),
+                    video: clip.video_path.clone(),
+                    effect: clip
+                        .reveal_effect
+                        .unwrap_or(save_file.settings.reveal_effect),
+                    challenge: clip.challenge.unwrap_or(save_file.settings.challenge),
                 })
         })
         .collect::<Result<_, _>>()?;
 
-    let mut ffmpeg_cmd = ffmpeg_command(
-        save_file.settings.duration,
-        countdown_duration,
-        countdown,
-        &items,
-        &args.output,
-    );
+    let mut ffmpeg_cmd = if args.audio_only {
+        let format = AudioFormat::from_path(&output).ok_or_else(|| {
+            eyre!(
+                "audio output must be one of: {}",
+                AudioFormat::EXTENSIONS.join(", ")
+            )
+        })?;
+        let cue = match countdown {
+            Some(countdown) => AudioCue::Countdown(countdown),
+            None => AudioCue::Tick,
+        };
+        audio_ffmpeg_command(
+            save_file.settings.duration,
+            countdown_duration,
+            cue,
+            &items,
+            format,
+            &output,
+        )
+    } else {
+        ffmpeg_command(
+            save_file.settings.duration,
+            countdown_duration,
+            countdown.expect("countdown is present for video exports"),
+            &items,
+            &output,
+        )
+    };
     if let Some(threads) = args.threads {
         ffmpeg_cmd.arg("-threads").arg(threads.to_string());
     }
@@ -85,8 +126,14 @@ fn main() -> color_eyre::Result<()> {
             .unwrap(),
     );
 
+    // Video exports report their progress in frames, audio exports in microseconds
+    let (progress_key, progress_unit) = if args.audio_only {
+        ("out_time_us", 1_000_000)
+    } else {
+        ("frame", 25)
+    };
     let progress_bar = indicatif::ProgressBar::new(
-        (25 * save_file.settings.duration as usize * items.len()) as u64,
+        (progress_unit * save_file.settings.duration as usize * items.len()) as u64,
     );
     progress_bar.set_style(
         ProgressStyle::default_bar()
@@ -104,11 +151,11 @@ fn main() -> color_eyre::Result<()> {
         let value = &line[(eq + 1)..];
 
         match key {
-            "frame" => {
-                let value: u64 = value
-                    .parse()
-                    .wrap_err(eyre!("frame was not an integer: {}", value))?;
-                progress_bar.set_position(value);
+            // ffmpeg can report the time as N/A for audio-only outputs, it is skipped
+            _ if key == progress_key => {
+                if let Ok(value) = value.parse() {
+                    progress_bar.set_position(value);
+                }
             }
             "progress" => {
                 if value == "end" {
@@ -1,3 +1,4 @@
+use bt_save::{Challenge, RevealEffect};
 use std::{
     ffi::OsString,
     path::{Path, PathBuf},
@@ -5,29 +6,232 @@ use std::{
     time::Duration,
 };
 
+pub mod media_info;
+pub mod playlist;
+
 fn fade_scale_stream(input: usize, output: usize, duration: u32) -> String {
     format!("[{}:v]scale=1920:1080:force_original_aspect_ratio=decrease,pad=1920:1080:(ow-iw)/2:(oh-ih)/2,setsar=1,fade=t=out:st={}:d=1[v{}]",input, duration - 1, output)
 }
 
-fn fade_audio_stream(input: usize, output: usize, duration: u32) -> String {
+/// Shows the image during the countdown with the effect, and the countdown in a corner
+fn countdown_effect_stream(
+    input: usize,
+    index: usize,
+    output: usize,
+    effect: RevealEffect,
+    duration: u32,
+) -> String {
+    let mut filter = format!("[{}:v]scale=1920:1080:force_original_aspect_ratio=decrease,pad=1920:1080:(ow-iw)/2:(oh-ih)/2,setsar=1,format=yuv420p", input);
+    match effect {
+        RevealEffect::Hidden => unreachable!("the countdown is shown alone without effects"),
+        RevealEffect::Blur => {
+            filter += &format!(
+                ",split[b{0}][s{0}];[b{0}]boxblur=40:5[bb{0}];[bb{0}][s{0}]blend=all_expr='A*(1-min(T/{1},1))+B*min(T/{1},1)'",
+                index, duration
+            );
+        }
+        RevealEffect::Pixelate => {
+            // The image sharpens in steps, each step halving the size of the pixels
+            const BLOCKS: [u32; 4] = [48, 24, 12, 6];
+            let step = duration as f32 / BLOCKS.len() as f32;
+
+            filter += &format!(",split={}", BLOCKS.len());
+            for stage in 0..BLOCKS.len() {
+                filter += &format!("[p{}_{}]", index, stage);
+            }
+            for (stage, block) in BLOCKS.iter().enumerate() {
+                filter += &format!(
+                    ";[p{0}_{1}]trim=start={2}:end={3},setpts=PTS-STARTPTS,scale=iw/{4}:ih/{4},scale=1920:1080:flags=neighbor[q{0}_{1}]",
+                    index,
+                    stage,
+                    step * stage as f32,
+                    step * (stage + 1) as f32,
+                    block
+                );
+            }
+            filter += ";";
+            for stage in 0..BLOCKS.len() {
+                filter += &format!("[q{}_{}]", index, stage);
+            }
+            filter += &format!("concat=n={}:v=1:a=0", BLOCKS.len());
+        }
+        RevealEffect::Zoom => {
+            filter += &format!(
+                ",zoompan=z='max(1,4-3*on/{})':x='iw/2-(iw/zoom/2)':y='ih/2-(ih/zoom/2)':d=1:s=1920x1080:fps=25,setsar=1",
+                duration * 25
+            );
+        }
+    }
+    filter += &format!(
+        "[e{0}];[0:v]scale=480:-2[c{0}];[e{0}][c{0}]overlay=W-w-40:H-h-40:shortest=1,fade=t=out:st={1}:d=1[v{2}]",
+        index,
+        duration - 1,
+        output
+    );
+    filter
+}
+
+fn waveform_stream(input: usize, output: usize, start: u32, duration: u32) -> String {
+    format!("[{}:a]atrim=start={},asetpts=PTS-STARTPTS,showwaves=s=1920x1080:mode=cline:rate=25,format=yuv420p,setsar=1,fade=t=out:st={}:d=1[v{}]", input, start, duration - 1, output)
+}
+
+/// Range of speeds a single atempo filter accepts
+const ATEMPO_RANGE: (f64, f64) = (0.5, 2.);
+
+/// Changes the speed of the music without changing its pitch, chaining atempo filters for the
+/// speeds a single one does not accept
+fn atempo_filter(percent: u32) -> String {
+    let (min, max) = ATEMPO_RANGE;
+    let mut filter = String::new();
+    let mut factor = percent as f64 / 100.;
+    while factor > max {
+        filter += &format!("atempo={},", max);
+        factor /= max;
+    }
+    while factor < min {
+        filter += &format!("atempo={},", min);
+        factor /= min;
+    }
+    filter + &format!("atempo={},", factor)
+}
+
+/// Duration of the music needed to produce a clip of the duration with the challenge
+fn challenge_input_duration(challenge: Challenge, duration: u32) -> u32 {
+    match challenge {
+        Challenge::SpedUp { percent } => (duration * percent).div_ceil(100),
+        _ => duration,
+    }
+}
+
+fn challenge_filter(challenge: Challenge, duration: u32) -> String {
+    match challenge {
+        Challenge::Normal => String::new(),
+        Challenge::Reversed => "areverse,".into(),
+        Challenge::SpedUp { percent } => atempo_filter(percent),
+        Challenge::IntroOnly { seconds } => {
+            format!("atrim=0:{},apad=whole_dur={},", seconds, duration)
+        }
+    }
+}
+
+fn fade_audio_stream(input: usize, output: usize, duration: u32, challenge: Challenge) -> String {
     format!(
-        "[{}:a]afade=t=out:st={}:d=1[a{}]",
+        "[{}:a]{}afade=t=out:st={}:d=1[a{}]",
         input,
+        challenge_filter(challenge, duration),
         duration - 1,
         output
     )
 }
 
-pub fn clip_duration_command(clip: &Path) -> Command {
-    let mut command = Command::new("ffprobe");
-    command.arg("-i").arg(clip).args(&[
-        "-show_entries",
-        "format=duration",
-        "-v",
-        "quiet",
-        "-of",
-        "csv=p=0",
-    ]);
+pub const VIDEO_EXTENSIONS: &[&str] = &["mp4", "mkv", "webm", "mov", "avi"];
+
+/// Returns true if the file is a video, judging from its extension
+pub fn is_video(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| {
+            VIDEO_EXTENSIONS
+                .iter()
+                .any(|video| video.eq_ignore_ascii_case(ext))
+        })
+        .unwrap_or(false)
+}
+
+/// A clip of the timeline to export
+#[derive(Debug, Clone)]
+pub struct Item {
+    pub offset: Duration,
+    pub music: PathBuf,
+    /// When missing, and there is no video, the reveal shows the waveform of the music
+    pub image: Option<PathBuf>,
+    /// Replaces the image for the reveal, starting at the same offset as the music
+    pub video: Option<PathBuf>,
+    /// Only applies when the reveal is an image
+    pub effect: RevealEffect,
+    pub challenge: Challenge,
+}
+
+/// Countdown length used for the generated tick when no countdown is available
+pub const DEFAULT_TICK_DURATION: u32 = 5;
+
+/// Audio containers supported by the audio-only export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioFormat {
+    Mp3,
+    Ogg,
+    Flac,
+    Wav,
+}
+
+impl AudioFormat {
+    pub const EXTENSIONS: &'static [&'static str] = &["mp3", "ogg", "flac", "wav"];
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "mp3" => Some(Self::Mp3),
+            "ogg" => Some(Self::Ogg),
+            "flac" => Some(Self::Flac),
+            "wav" => Some(Self::Wav),
+            _ => None,
+        }
+    }
+
+    fn codec(self) -> &'static str {
+        match self {
+            Self::Mp3 => "libmp3lame",
+            Self::Ogg => "libvorbis",
+            Self::Flac => "flac",
+            Self::Wav => "pcm_s16le",
+        }
+    }
+}
+
+/// What is heard during the countdown of an audio-only export
+#[derive(Debug, Clone, Copy)]
+pub enum AudioCue<'a> {
+    /// The audio track of the countdown video
+    Countdown(&'a Path),
+    /// A generated tick every second
+    Tick,
+}
+
+fn cue_audio_stream(
+    cue: AudioCue,
+    countdown_duration: u32,
+    clip_duration: u32,
+    output: usize,
+) -> String {
+    let source = match cue {
+        AudioCue::Countdown(_) => format!("[0:a]atrim=0:{}", countdown_duration),
+        AudioCue::Tick => format!(
+            "aevalsrc='0.4*sin(2*PI*880*t)*lt(mod(t,1),0.08)':d={}",
+            countdown_duration
+        ),
+    };
+    format!("{},apad=whole_dur={}[c{}]", source, clip_duration, output)
+}
+
+pub const EXTRACTED_SAMPLE_RATE: u32 = 44100;
+pub const EXTRACTED_CHANNELS: u16 = 2;
+
+/// Decodes the audio track of a media from `offset` to signed 16 bits little endian PCM on the
+/// standard output, with [EXTRACTED_CHANNELS] channels at [EXTRACTED_SAMPLE_RATE]
+pub fn extract_audio_command(media: &Path, offset: Duration) -> Command {
+    let mut command = Command::new("ffmpeg");
+    command
+        .args(["-v", "error", "-ss"])
+        .arg(offset.as_secs_f64().to_string())
+        .arg("-i")
+        .arg(media)
+        .arg("-vn")
+        .arg("-ac")
+        .arg(EXTRACTED_CHANNELS.to_string())
+        .arg("-ar")
+        .arg(EXTRACTED_SAMPLE_RATE.to_string())
+        .args(["-f", "s16le", "-"])
+        .stdout(Stdio::piped());
     command
 }
 
@@ -35,38 +239,87 @@ pub fn ffmpeg_command(
     clip_duration: u32,
     countdown_duration: u32,
     countdown: &Path,
-    items: &[(Duration, PathBuf, PathBuf)],
+    items: &[Item],
     output: &Path,
 ) -> Command {
     let loop_dur = clip_duration - countdown_duration;
     let looping_duration: OsString = loop_dur.to_string().into();
-    let clip_duration_str: OsString = clip_duration.to_string().into();
 
     let mut ffmpeg = Command::new("ffmpeg");
     ffmpeg.arg("-i").arg(countdown);
 
     let mut filter = String::new();
+    let mut input = 1;
+
+    for (index, item) in items.iter().enumerate() {
+        let effect_input = match (&item.video, &item.image, item.effect) {
+            (None, Some(image), effect) if effect != RevealEffect::Hidden => {
+                ffmpeg
+                    .arg("-loop")
+                    .arg("1")
+                    .arg("-t")
+                    .arg(countdown_duration.to_string())
+                    .arg("-i")
+                    .arg(image);
+                input += 1;
+                Some(input - 1)
+            }
+            _ => None,
+        };
+
+        let reveal_input = match (&item.video, &item.image) {
+            (Some(video), _) => {
+                ffmpeg
+                    .arg("-ss")
+                    .arg((item.offset.as_secs_f64() + countdown_duration as f64).to_string())
+                    .arg("-t")
+                    .arg(&looping_duration)
+                    .arg("-i")
+                    .arg(video);
+                input += 1;
+                Some(input - 1)
+            }
+            (None, Some(image)) => {
+                ffmpeg
+                    .arg("-loop")
+                    .arg("1")
+                    .arg("-t")
+                    .arg(&looping_duration)
+                    .arg("-i")
+                    .arg(image);
+                input += 1;
+                Some(input - 1)
+            }
+            (None, None) => None,
+        };
 
-    for (index, (offset, music_path, image_path)) in items.iter().enumerate() {
         ffmpeg
-            .arg("-loop")
-            .arg("1")
-            .arg("-t")
-            .arg(&looping_duration)
-            .arg("-i")
-            .arg(image_path)
             .arg("-ss")
-            .arg(offset.as_secs().to_string())
+            .arg(item.offset.as_secs_f64().to_string())
             .arg("-t")
-            .arg(&clip_duration_str)
+            .arg(challenge_input_duration(item.challenge, clip_duration).to_string())
             .arg("-i")
-            .arg(music_path);
+            .arg(&item.music);
+        let music_input = input;
+        input += 1;
 
-        filter += &fade_scale_stream(0, 2 * index, countdown_duration);
+        filter += &match effect_input {
+            Some(effect_input) => countdown_effect_stream(
+                effect_input,
+                index,
+                2 * index,
+                item.effect,
+                countdown_duration,
+            ),
+            None => fade_scale_stream(0, 2 * index, countdown_duration),
+        };
         filter += ";";
-        filter += &fade_scale_stream(2 * index + 1, 2 * index + 1, loop_dur);
+        filter += &match reveal_input {
+            Some(reveal_input) => fade_scale_stream(reveal_input, 2 * index + 1, loop_dur),
+            None => waveform_stream(music_input, 2 * index + 1, countdown_duration, loop_dur),
+        };
         filter += ";";
-        filter += &fade_audio_stream((index + 1) * 2, index, clip_duration);
+        filter += &fade_audio_stream(music_input, index, clip_duration, item.challenge);
         filter += ";";
     }
 
@@ -97,10 +350,79 @@ pub fn ffmpeg_command(
     ffmpeg
 }
 
+pub fn audio_ffmpeg_command(
+    clip_duration: u32,
+    countdown_duration: u32,
+    cue: AudioCue,
+    items: &[Item],
+    format: AudioFormat,
+    output: &Path,
+) -> Command {
+    let mut ffmpeg = Command::new("ffmpeg");
+    let first_clip = match cue {
+        AudioCue::Countdown(countdown) => {
+            ffmpeg.arg("-i").arg(countdown);
+            1
+        }
+        AudioCue::Tick => 0,
+    };
+
+    let mut filter = String::new();
+
+    for (index, item) in items.iter().enumerate() {
+        ffmpeg
+            .arg("-ss")
+            .arg(item.offset.as_secs_f64().to_string())
+            .arg("-t")
+            .arg(challenge_input_duration(item.challenge, clip_duration).to_string())
+            .arg("-i")
+            .arg(&item.music);
+
+        filter += &fade_audio_stream(first_clip + index, index, clip_duration, item.challenge);
+        filter += ";";
+        filter += &cue_audio_stream(cue, countdown_duration, clip_duration, index);
+        filter += ";";
+        filter += &format!(
+            "[a{0}][c{0}]amix=inputs=2:duration=first,volume=2[m{0}];",
+            index
+        );
+    }
+
+    let audio_streams: String = (0..items.len()).map(|i| format!("[m{}]", i)).collect();
+    filter += &audio_streams;
+    filter += &format!("concat=n={}:v=0:a=1[a]", items.len());
+
+    ffmpeg
+        .arg("-filter_complex")
+        .arg(&filter)
+        .arg("-map")
+        .arg("[a]")
+        .arg("-c:a")
+        .arg(format.codec())
+        .arg("-v")
+        .arg("error")
+        .arg("-progress")
+        .arg("-")
+        .arg("-y")
+        .arg(output)
+        .stdout(Stdio::piped());
+
+    ffmpeg
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn it_works() {
         assert_eq!(2 + 2, 4);
     }
+
+    #[test]
+    fn chained_atempo() {
+        assert_eq!(atempo_filter(150), "atempo=1.5,");
+        assert_eq!(atempo_filter(500), "atempo=2,atempo=2,atempo=1.25,");
+        assert_eq!(atempo_filter(40), "atempo=0.5,atempo=0.8,");
+    }
 }
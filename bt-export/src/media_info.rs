@@ -0,0 +1,323 @@
+use std::{fs::File, path::Path, process::Command, time::Duration};
+use symphonia::core::{
+    codecs::CODEC_TYPE_NULL,
+    errors::Error as SymphoniaError,
+    formats::{FormatOptions, FormatReader},
+    io::MediaSourceStream,
+    meta::{MetadataOptions, MetadataRevision, StandardTagKey, StandardVisualKey},
+    probe::{Hint, ProbeResult},
+    units::TimeBase,
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("the media could not be read: {0}")]
+    Unreadable(String),
+}
+
+/// Description of the audio of a media file
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaInfo {
+    pub duration: Duration,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u16>,
+    pub codec: Option<String>,
+}
+
+/// Reads the audio description of a media, using ffprobe only when the format is not supported.
+///
+/// Videos are probed with ffprobe first: symphonia only reads the length of the audio track, when
+/// the video stream can be longer.
+pub fn probe(path: &Path) -> Result<MediaInfo, Error> {
+    if crate::is_video(path) {
+        return probe_ffprobe(path).or_else(|ffprobe| {
+            probe_native(path)
+                .map_err(|native| Error::Unreadable(format!("{} (symphonia: {})", ffprobe, native)))
+        });
+    }
+
+    match probe_native(path) {
+        Ok(info) => Ok(info),
+        Err(native) => probe_ffprobe(path)
+            .map_err(|ffprobe| Error::Unreadable(format!("{} (ffprobe: {})", native, ffprobe))),
+    }
+}
+
+/// Opens the container of a media, to read its packets with symphonia
+pub fn open_format(path: &Path) -> Result<Box<dyn FormatReader>, SymphoniaError> {
+    Ok(probe_format(path)?.format)
+}
+
+fn probe_format(path: &Path) -> Result<ProbeResult, SymphoniaError> {
+    let source = MediaSourceStream::new(Box::new(File::open(path)?), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(extension);
+    }
+
+    symphonia::default::get_probe().format(
+        &hint,
+        source,
+        &FormatOptions::default(),
+        &MetadataOptions::default(),
+    )
+}
+
+/// An image embedded in a media
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cover {
+    pub media_type: String,
+    pub data: Vec<u8>,
+}
+
+impl Cover {
+    /// Extension of a file holding the image
+    pub fn extension(&self) -> Option<&'static str> {
+        match self.media_type.as_str() {
+            "image/jpeg" | "image/jpg" => Some("jpg"),
+            "image/png" => Some("png"),
+            "image/webp" => Some("webp"),
+            "image/bmp" => Some("bmp"),
+            _ => None,
+        }
+    }
+}
+
+/// Tags of a music, from ID3 or Vorbis comments for example
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Tags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    /// The front cover, or else the first embedded image
+    pub cover: Option<Cover>,
+}
+
+impl Tags {
+    /// Takes the values present in the revision, replacing the ones already read
+    fn apply(&mut self, revision: &MetadataRevision) {
+        for tag in revision.tags() {
+            let field = match tag.std_key {
+                Some(StandardTagKey::TrackTitle) => &mut self.title,
+                Some(StandardTagKey::Artist) => &mut self.artist,
+                Some(StandardTagKey::Album) => &mut self.album,
+                _ => continue,
+            };
+            let value = tag.value.to_string();
+            let value = value.trim();
+            if !value.is_empty() {
+                *field = Some(value.to_string());
+            }
+        }
+
+        let visuals = revision.visuals();
+        let cover = visuals
+            .iter()
+            .find(|visual| visual.usage == Some(StandardVisualKey::FrontCover))
+            .or_else(|| visuals.first());
+        if let Some(cover) = cover {
+            self.cover = Some(Cover {
+                media_type: cover.media_type.clone(),
+                data: cover.data.to_vec(),
+            });
+        }
+    }
+}
+
+/// Reads the tags of a music, the tags of the container taking precedence over the ones in front
+/// of it (like ID3v2 in a mp3)
+pub fn read_tags(path: &Path) -> Result<Tags, Error> {
+    let mut probed = probe_format(path).map_err(|e| Error::Unreadable(e.to_string()))?;
+
+    let mut tags = Tags::default();
+    if let Some(revision) = probed
+        .metadata
+        .get()
+        .as_ref()
+        .and_then(|metadata| metadata.current())
+    {
+        tags.apply(revision);
+    }
+    if let Some(revision) = probed.format.metadata().current() {
+        tags.apply(revision);
+    }
+
+    Ok(tags)
+}
+
+fn probe_native(path: &Path) -> Result<MediaInfo, SymphoniaError> {
+    let mut format = open_format(path)?;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or(SymphoniaError::Unsupported("no audio track"))?;
+    let params = track.codec_params.clone();
+    let track_id = track.id;
+
+    let time_base = params
+        .time_base
+        .or_else(|| params.sample_rate.map(|rate| TimeBase::new(1, rate)))
+        .ok_or(SymphoniaError::Unsupported("no time base"))?;
+
+    // Some formats, like mp3 without a Xing header, don't store their length and need to be read
+    let frames = match params.n_frames {
+        Some(frames) => frames,
+        None => {
+            let mut frames = 0;
+            loop {
+                match format.next_packet() {
+                    Ok(packet) if packet.track_id() == track_id => frames += packet.dur,
+                    Ok(_) => continue,
+                    Err(SymphoniaError::IoError(e))
+                        if e.kind() == std::io::ErrorKind::UnexpectedEof =>
+                    {
+                        break
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            frames
+        }
+    };
+    let time = time_base.calc_time(frames);
+
+    Ok(MediaInfo {
+        duration: Duration::from_secs(time.seconds) + Duration::from_secs_f64(time.frac),
+        sample_rate: params.sample_rate,
+        channels: params.channels.map(|c| c.count() as u16),
+        codec: symphonia::default::get_codecs()
+            .get_codec(params.codec)
+            .map(|codec| codec.short_name.to_string()),
+    })
+}
+
+fn probe_ffprobe(path: &Path) -> Result<MediaInfo, String> {
+    let output = Command::new("ffprobe")
+        .arg("-i")
+        .arg(path)
+        .args([
+            "-show_entries",
+            "format=duration:stream=codec_name,sample_rate,channels",
+            "-select_streams",
+            "a:0",
+            "-v",
+            "quiet",
+            "-of",
+            "default=noprint_wrappers=1",
+        ])
+        .output()
+        .map_err(|e| format!("could not run ffprobe: {}", e))?;
+    if !output.status.success() {
+        return Err("ffprobe failed".into());
+    }
+
+    parse_ffprobe(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| "ffprobe gave an invalid output".into())
+}
+
+/// Parses the `key=value` output of ffprobe, the audio stream being optional (e.g. for a silent
+/// video)
+fn parse_ffprobe(output: &str) -> Option<MediaInfo> {
+    let mut duration = None;
+    let mut info = MediaInfo {
+        duration: Duration::default(),
+        sample_rate: None,
+        channels: None,
+        codec: None,
+    };
+
+    for line in output.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let (key, value) = line.split_at(line.find('=')?);
+        let value = &value[1..];
+        match key {
+            "duration" => duration = Some(value.parse::<f64>().ok()?),
+            "sample_rate" => info.sample_rate = value.parse().ok(),
+            "channels" => info.channels = value.parse().ok(),
+            "codec_name" => info.codec = Some(value.to_string()),
+            _ => continue,
+        }
+    }
+
+    Some(MediaInfo {
+        duration: Duration::from_secs_f64(duration?),
+        ..info
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ffprobe_output() {
+        assert_eq!(
+            parse_ffprobe("codec_name=mp3\nsample_rate=44100\nchannels=2\nduration=183.066122\n"),
+            Some(MediaInfo {
+                duration: Duration::from_secs_f64(183.066122),
+                sample_rate: Some(44100),
+                channels: Some(2),
+                codec: Some("mp3".into()),
+            })
+        );
+        assert_eq!(
+            parse_ffprobe("duration=10.000000\n").map(|info| (info.duration, info.codec)),
+            Some((Duration::from_secs(10), None))
+        );
+        assert_eq!(parse_ffprobe("duration=N/A\n"), None);
+    }
+
+    #[test]
+    fn tags_from_revisions() {
+        use symphonia::core::meta::{MetadataBuilder, Tag, Value, Visual};
+
+        let visual = |usage, data: &[u8]| Visual {
+            media_type: "image/png".into(),
+            dimensions: None,
+            bits_per_pixel: None,
+            color_mode: None,
+            usage,
+            tags: Vec::new(),
+            data: data.into(),
+        };
+
+        let mut id3 = MetadataBuilder::new();
+        id3.add_tag(Tag::new(
+            Some(StandardTagKey::TrackTitle),
+            "TIT2",
+            Value::from("Old title"),
+        ))
+        .add_tag(Tag::new(
+            Some(StandardTagKey::Artist),
+            "TPE1",
+            Value::from("Artist"),
+        ))
+        .add_visual(visual(None, b"other"))
+        .add_visual(visual(Some(StandardVisualKey::FrontCover), b"front"));
+        let mut vorbis = MetadataBuilder::new();
+        vorbis
+            .add_tag(Tag::new(
+                Some(StandardTagKey::TrackTitle),
+                "TITLE",
+                Value::from("Title"),
+            ))
+            .add_tag(Tag::new(
+                Some(StandardTagKey::Album),
+                "ALBUM",
+                Value::from("  "),
+            ));
+
+        let mut tags = Tags::default();
+        tags.apply(&id3.metadata());
+        tags.apply(&vorbis.metadata());
+
+        assert_eq!(tags.title.as_deref(), Some("Title"));
+        assert_eq!(tags.artist.as_deref(), Some("Artist"));
+        assert_eq!(tags.album, None);
+        let cover = tags.cover.unwrap();
+        assert_eq!(cover.data, b"front");
+        assert_eq!(cover.extension(), Some("png"));
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{challenge_input_duration, AudioFormat};
+use bt_save::{ClipSave, Settings};
+use std::{fmt::Write, path::Path, time::Duration};
+
+/// Formats the timeline can be exported to, to rehearse it in an audio player
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaylistFormat {
+    M3u,
+    Cue,
+}
+
+impl PlaylistFormat {
+    pub const EXTENSIONS: &'static [&'static str] = &["m3u", "m3u8", "cue"];
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "m3u" | "m3u8" => Some(Self::M3u),
+            "cue" => Some(Self::Cue),
+            _ => None,
+        }
+    }
+}
+
+fn display_title(clip: &ClipSave) -> String {
+    match &clip.artist {
+        Some(artist) => format!("{} - {}", artist, clip.title),
+        None => clip.title.clone(),
+    }
+}
+
+/// A playlist of the music of the clips, each one starting at its offset and playing for as long
+/// as in the export (VLC reads the start and stop times)
+pub fn m3u(clips: &[&ClipSave], settings: &Settings) -> String {
+    let mut playlist = String::from("#EXTM3U\n");
+
+    for clip in clips {
+        let challenge = clip.challenge.unwrap_or(settings.challenge);
+        let played = challenge_input_duration(challenge, settings.duration);
+        let start = clip.offset.as_secs_f64();
+        let stop = (clip.offset + Duration::from_secs(played as u64)).as_secs_f64();
+
+        // Writing to a String can't fail
+        let _ = writeln!(
+            playlist,
+            "#EXTINF:{},{}",
+            settings.duration,
+            display_title(clip)
+        );
+        let _ = writeln!(playlist, "#EXTVLCOPT:start-time={}", start);
+        let _ = writeln!(playlist, "#EXTVLCOPT:stop-time={}", stop);
+        let _ = writeln!(playlist, "{}", clip.music_path.display());
+    }
+
+    playlist
+}
+
+/// CUE sheets can't escape quotes
+fn cue_text(text: &str) -> String {
+    text.replace('"', "'")
+}
+
+/// A CUE sheet of the audio-only export in `audio`, with a track per clip
+pub fn cue(clips: &[&ClipSave], settings: &Settings, audio: &Path) -> String {
+    let file_type = match AudioFormat::from_path(audio) {
+        Some(AudioFormat::Mp3) => "MP3",
+        _ => "WAVE",
+    };
+    let file_name = audio
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+
+    let mut sheet = String::new();
+    let _ = writeln!(sheet, "FILE \"{}\" {}", cue_text(&file_name), file_type);
+    for (index, clip) in clips.iter().enumerate() {
+        // Each clip lasts exactly the clip duration in the export
+        let start = index as u64 * settings.duration as u64;
+
+        let _ = writeln!(sheet, "  TRACK {:02} AUDIO", index + 1);
+        let _ = writeln!(sheet, "    TITLE \"{}\"", cue_text(&clip.title));
+        if let Some(artist) = &clip.artist {
+            let _ = writeln!(sheet, "    PERFORMER \"{}\"", cue_text(artist));
+        }
+        let _ = writeln!(sheet, "    INDEX 01 {:02}:{:02}:00", start / 60, start % 60);
+    }
+
+    sheet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bt_save::Challenge;
+
+    fn clip(title: &str, artist: Option<&str>, offset: u64) -> ClipSave {
+        ClipSave {
+            title: title.into(),
+            image_path: None,
+            music_path: format!("/music/{}.mp3", title).into(),
+            offset: Duration::from_secs(offset),
+            video_path: None,
+            reveal_effect: None,
+            challenge: None,
+            music_hash: None,
+            artist: artist.map(Into::into),
+            album: None,
+        }
+    }
+
+    fn settings() -> Settings {
+        Settings {
+            duration: 40,
+            countdown: None,
+            reveal_effect: Default::default(),
+            challenge: Default::default(),
+        }
+    }
+
+    #[test]
+    fn m3u_start_and_stop() {
+        let first = clip("first", Some("Band"), 30);
+        let second = ClipSave {
+            challenge: Some(Challenge::SpedUp { percent: 150 }),
+            ..clip("second", None, 0)
+        };
+        let third = ClipSave {
+            offset: Duration::from_millis(12_300),
+            ..clip("third", None, 0)
+        };
+
+        assert_eq!(
+            m3u(&[&first, &second, &third], &settings()),
+            "#EXTM3U\n\
+             #EXTINF:40,Band - first\n\
+             #EXTVLCOPT:start-time=30\n\
+             #EXTVLCOPT:stop-time=70\n\
+             /music/first.mp3\n\
+             #EXTINF:40,second\n\
+             #EXTVLCOPT:start-time=0\n\
+             #EXTVLCOPT:stop-time=60\n\
+             /music/second.mp3\n\
+             #EXTINF:40,third\n\
+             #EXTVLCOPT:start-time=12.3\n\
+             #EXTVLCOPT:stop-time=52.3\n\
+             /music/third.mp3\n"
+        );
+    }
+
+    #[test]
+    fn cue_tracks() {
+        let first = clip("first", Some("Band"), 30);
+        let second = clip("\"second\"", None, 0);
+
+        assert_eq!(
+            cue(&[&first, &second], &settings(), Path::new("/out/test.flac")),
+            "FILE \"test.flac\" WAVE\n  \
+               TRACK 01 AUDIO\n    \
+                 TITLE \"first\"\n    \
+                 PERFORMER \"Band\"\n    \
+                 INDEX 01 00:00:00\n  \
+               TRACK 02 AUDIO\n    \
+                 TITLE \"'second'\"\n    \
+                 INDEX 01 00:40:00\n"
+        );
+    }
+}
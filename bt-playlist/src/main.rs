@@ -0,0 +1,127 @@
+use bt_export::playlist::PlaylistFormat;
+use bt_save::{SaveFile, Settings};
+use color_eyre::eyre::{self, eyre, WrapErr};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+enum Args {
+    /// Adds the songs of a M3U, XSPF or CSV playlist to a project, creating it if needed
+    Import {
+        #[structopt(short = "i", long = "input")]
+        playlist: PathBuf,
+        #[structopt(short = "o", long = "output")]
+        save_file: PathBuf,
+        /// Clip duration of a new project
+        #[structopt(short = "d", long = "duration", default_value = "30")]
+        duration: u32,
+    },
+    /// Writes the timeline of a project as a M3U playlist or a CUE sheet, to rehearse it
+    Export {
+        #[structopt(short = "i", long = "input")]
+        save_file: PathBuf,
+        /// The format is chosen from the extension: m3u, m3u8 or cue
+        #[structopt(short = "o", long = "output")]
+        playlist: PathBuf,
+        /// Audio-only export described by the CUE sheet, defaults to the output with a mp3
+        /// extension
+        #[structopt(long = "audio")]
+        audio: Option<PathBuf>,
+    },
+}
+
+fn import(playlist: PathBuf, output: PathBuf, duration: u32) -> color_eyre::Result<()> {
+    let mut save = if output.exists() {
+        bt_save::load(&output).wrap_err("could not open save file")?
+    } else {
+        SaveFile {
+            clips: Vec::new(),
+            timeline: Vec::new(),
+            settings: Settings {
+                duration,
+                countdown: None,
+                reveal_effect: Default::default(),
+                challenge: Default::default(),
+            },
+        }
+    };
+
+    let entries = bt_save::playlist::read(&playlist).wrap_err("could not read playlist")?;
+    let existing: HashSet<_> = save.clips.iter().map(|clip| clip.title.clone()).collect();
+    let import = bt_save::playlist::resolve(entries, &existing);
+
+    println!(
+        "Imported {} clip(s) in {} timeline slot(s)",
+        import.clips.len(),
+        import.timeline.len()
+    );
+    if !import.unresolved.is_empty() {
+        eprintln!("{} path(s) could not be resolved:", import.unresolved.len());
+        for path in &import.unresolved {
+            eprintln!("  {}", path.display());
+        }
+    }
+
+    save.clips.extend(import.clips);
+    save.timeline.extend(import.timeline.into_iter().map(Some));
+    bt_save::store(&output, &save).wrap_err("could not write save file")?;
+
+    Ok(())
+}
+
+fn export(save_file: PathBuf, output: PathBuf, audio: Option<PathBuf>) -> color_eyre::Result<()> {
+    let format = PlaylistFormat::from_path(&output).ok_or_else(|| {
+        eyre!(
+            "output must be one of: {}",
+            PlaylistFormat::EXTENSIONS.join(", ")
+        )
+    })?;
+    let save = bt_save::load(&save_file).wrap_err("could not open save file")?;
+
+    let clips: HashMap<_, _> = save.clips.iter().map(|clip| (&clip.title, clip)).collect();
+    let timeline: Vec<_> = save
+        .timeline
+        .iter()
+        .flatten()
+        .map(|title| {
+            clips
+                .get(title)
+                .copied()
+                .ok_or_else(|| eyre!("Clip '{}' does not exist", title))
+        })
+        .collect::<Result<_, _>>()?;
+    if timeline.is_empty() {
+        eyre::bail!("the timeline is empty");
+    }
+
+    let playlist = match format {
+        PlaylistFormat::M3u => bt_export::playlist::m3u(&timeline, &save.settings),
+        PlaylistFormat::Cue => {
+            let audio = audio.unwrap_or_else(|| output.with_extension("mp3"));
+            bt_export::playlist::cue(&timeline, &save.settings, &audio)
+        }
+    };
+    std::fs::write(&output, playlist).wrap_err("could not write playlist")?;
+
+    Ok(())
+}
+
+fn main() -> color_eyre::Result<()> {
+    color_eyre::install()?;
+
+    match Args::from_args() {
+        Args::Import {
+            playlist,
+            save_file,
+            duration,
+        } => import(playlist, save_file, duration),
+        Args::Export {
+            save_file,
+            playlist,
+            audio,
+        } => export(save_file, playlist, audio),
+    }
+}
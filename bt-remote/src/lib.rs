@@ -0,0 +1,309 @@
+//! A small HTTP server letting a phone on the local network drive the builder: the page served
+//! at `/` sends the commands and shows the state of the timeline or of the presentation.
+//!
+//! Every request must carry the random token of the server in its query, `/?token=<token>`, so
+//! that only the ones given the address can use it.
+
+use serde::Serialize;
+use std::{
+    net::{IpAddr, SocketAddr, ToSocketAddrs, UdpSocket},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+const PAGE: &str = include_str!("remote.html");
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("could not start the server")]
+    Start(#[source] Box<dyn std::error::Error + Send + Sync>),
+    #[error("could not generate the access token")]
+    Token(#[from] getrandom::Error),
+}
+
+/// Number of random bytes of the access token
+const TOKEN_BYTES: usize = 16;
+
+fn new_token() -> Result<String, Error> {
+    let mut bytes = [0; TOKEN_BYTES];
+    getrandom::getrandom(&mut bytes)?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// An action requested by the remote
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Plays the whole timeline, or replays the clip of the presentation
+    Play,
+    /// Stops the timeline, or ends the presentation
+    Stop,
+    Present,
+    /// Reveals the answer, or goes to the next clip once it is revealed
+    Next,
+    /// Goes to the next clip without revealing the answer
+    Skip,
+    Back,
+    Pause,
+    PlayClip(usize),
+    StopClip(usize),
+}
+
+impl Command {
+    /// Commands are posted to `/play`, `/next`... or `/clips/<index>/play` for a clip of the
+    /// timeline
+    pub fn from_path(path: &str) -> Option<Self> {
+        let parts: Vec<_> = path.trim_matches('/').split('/').collect();
+        let command = match parts.as_slice() {
+            ["play"] => Self::Play,
+            ["stop"] => Self::Stop,
+            ["present"] => Self::Present,
+            ["next"] => Self::Next,
+            ["skip"] => Self::Skip,
+            ["back"] => Self::Back,
+            ["pause"] => Self::Pause,
+            ["clips", index, "play"] => Self::PlayClip(index.parse().ok()?),
+            ["clips", index, "stop"] => Self::StopClip(index.parse().ok()?),
+            _ => return None,
+        };
+        Some(command)
+    }
+}
+
+/// What the builder is doing, as shown on the remote
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct State {
+    /// Titles of the clips of the timeline, `None` for the empty entries
+    pub timeline: Vec<Option<String>>,
+    /// The whole timeline is playing
+    pub playing: bool,
+    /// Entries of the timeline being played on their own
+    pub playing_clips: Vec<usize>,
+    pub presentation: Option<PresentationState>,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct PresentationState {
+    /// Index of the clip among the played ones
+    pub round: usize,
+    pub rounds: usize,
+    pub clip: String,
+    pub artist: Option<String>,
+    pub revealed: bool,
+    pub paused: bool,
+    pub done: bool,
+    /// Seconds left in the countdown
+    pub remaining: u64,
+}
+
+/// The address of this computer on the local network, to show where the remote is. The socket
+/// is only used to find the route, nothing is sent.
+pub fn lan_ip() -> Option<IpAddr> {
+    let socket = UdpSocket::bind(("0.0.0.0", 0)).ok()?;
+    socket.connect(("192.0.2.1", 80)).ok()?;
+    socket.local_addr().ok().map(|addr| addr.ip())
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("invalid header")
+}
+
+fn header_value<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv(name))
+        .map(|header| header.value.as_str())
+}
+
+/// A page of another site is posting to the remote from the browser of someone on the network
+fn foreign_origin(request: &Request) -> bool {
+    match (
+        header_value(request, "Origin"),
+        header_value(request, "Host"),
+    ) {
+        (None, _) => false,
+        (Some(origin), Some(host)) => origin != format!("http://{}", host),
+        (Some(_), None) => true,
+    }
+}
+
+fn handle(request: Request, token: &str, state: &Mutex<State>, commands: &Sender<Command>) {
+    let (path, query) = match request.url().split_once('?') {
+        Some((path, query)) => (path, query),
+        None => (request.url(), ""),
+    };
+    let authorized = query
+        .split('&')
+        .any(|pair| pair.strip_prefix("token=") == Some(token));
+
+    let response = match (request.method(), path) {
+        _ if !authorized => Response::from_string(
+            "Forbidden: open the address shown in the builder, with its token",
+        )
+        .with_status_code(403),
+        (Method::Post, _) if foreign_origin(&request) => {
+            Response::from_string("Forbidden: cross-origin request").with_status_code(403)
+        }
+        (Method::Get, "/") => Response::from_string(PAGE)
+            .with_header(header("Content-Type", "text/html; charset=utf-8")),
+        (Method::Get, "/state") => {
+            let state = state.lock().expect("remote state poisoned");
+            Response::from_string(serde_json::to_string(&*state).expect("invalid state"))
+                .with_header(header("Content-Type", "application/json"))
+        }
+        (Method::Post, url) => match Command::from_path(url) {
+            Some(command) => {
+                // The builder is gone when the channel is closed, the server stops right after
+                let _ = commands.send(command);
+                Response::from_string("").with_status_code(204)
+            }
+            None => Response::from_string("Unknown command").with_status_code(404),
+        },
+        _ => Response::from_string("Not found").with_status_code(404),
+    };
+
+    if let Err(e) = request.respond(response) {
+        eprintln!("Could not answer the remote: {:?}", e);
+    }
+}
+
+/// The server, running in its own thread until it is dropped
+pub struct Remote {
+    server: Arc<Server>,
+    state: Arc<Mutex<State>>,
+    commands: Receiver<Command>,
+    thread: Option<JoinHandle<()>>,
+    token: String,
+}
+
+impl Remote {
+    pub fn start<A: ToSocketAddrs>(addr: A) -> Result<Self, Error> {
+        let token = new_token()?;
+        let server = Arc::new(Server::http(addr).map_err(Error::Start)?);
+        let state = Arc::new(Mutex::new(State::default()));
+        let (sender, commands) = mpsc::channel();
+
+        let thread = {
+            let server = server.clone();
+            let state = state.clone();
+            let token = token.clone();
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    handle(request, &token, &state, &sender);
+                }
+            })
+        };
+
+        Ok(Self {
+            server,
+            state,
+            commands,
+            thread: Some(thread),
+            token,
+        })
+    }
+
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.server.server_addr().to_ip()
+    }
+
+    /// Address of the page of the remote when the server is reached at `addr`
+    pub fn url(&self, addr: SocketAddr) -> String {
+        format!("http://{}/?token={}", addr, self.token)
+    }
+
+    /// Has to be in the query of every request
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    pub fn set_state(&self, state: State) {
+        *self.state.lock().expect("remote state poisoned") = state;
+    }
+
+    /// The commands received since the last call
+    pub fn commands(&self) -> impl Iterator<Item = Command> + '_ {
+        self.commands.try_iter()
+    }
+}
+
+impl Drop for Remote {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{Read, Write},
+        net::TcpStream,
+    };
+
+    fn request(addr: SocketAddr, method: &str, path: &str, headers: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: {}\r\n{}Content-Length: 0\r\nConnection: close\r\n\r\n",
+            method, path, addr, headers
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn commands_and_state() {
+        let remote = Remote::start(("127.0.0.1", 0)).unwrap();
+        let addr = remote.local_addr().unwrap();
+        let token = format!("?token={}", remote.token());
+        let send = |method, path: &str, headers| {
+            request(addr, method, &format!("{}{}", path, token), headers)
+        };
+
+        assert!(send("POST", "/next", "").starts_with("HTTP/1.1 204"));
+        assert!(send("POST", "/clips/2/play", "").starts_with("HTTP/1.1 204"));
+        assert!(send("POST", "/clips/two/play", "").starts_with("HTTP/1.1 404"));
+        // The page of the remote posts from its own origin, other sites are refused
+        let origin = format!("Origin: http://{}\r\n", addr);
+        assert!(send("POST", "/skip", &origin).starts_with("HTTP/1.1 204"));
+        let foreign = "Origin: http://example.com\r\n";
+        assert!(send("POST", "/stop", foreign).starts_with("HTTP/1.1 403"));
+        assert_eq!(
+            remote.commands().collect::<Vec<_>>(),
+            vec![Command::Next, Command::PlayClip(2), Command::Skip]
+        );
+
+        // Requests without the token are refused
+        assert!(request(addr, "POST", "/next", "").starts_with("HTTP/1.1 403"));
+        assert!(request(addr, "GET", "/?token=wrong", "").starts_with("HTTP/1.1 403"));
+        assert_eq!(remote.commands().count(), 0);
+
+        remote.set_state(State {
+            timeline: vec![Some("first".into()), None],
+            playing: true,
+            ..Default::default()
+        });
+        let response = send("GET", "/state", "");
+        let body = response.split("\r\n\r\n").nth(1).unwrap();
+        let state: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(state["timeline"], serde_json::json!(["first", null]));
+        assert_eq!(state["playing"], true);
+        assert_eq!(state["presentation"], serde_json::Value::Null);
+
+        assert!(send("GET", "/", "").contains("<html"));
+        assert_eq!(
+            remote.url(addr),
+            format!("http://{}/?token={}", addr, remote.token())
+        );
+    }
+}
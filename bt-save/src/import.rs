@@ -0,0 +1,130 @@
+use crate::{ClipSave, Error};
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Files of `dir` with one of the extensions, sorted by name
+pub fn list_files(dir: &Path, extensions: &[&str]) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let matches = path
+            .extension()
+            .and_then(OsStr::to_str)
+            .map(|ext| extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+            .unwrap_or(false);
+        if matches && path.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Title of a clip made from a file: its name without the extension, with underscores as spaces
+pub fn title_from_path(path: &Path) -> Option<String> {
+    let title = path.file_stem()?.to_string_lossy().replace('_', " ");
+    let title = title.trim();
+    if title.is_empty() {
+        None
+    } else {
+        Some(title.to_owned())
+    }
+}
+
+fn stem_key(path: &Path) -> Option<String> {
+    Some(path.file_stem()?.to_string_lossy().to_lowercase())
+}
+
+/// Makes a clip of each music, with the image that has the same file name without the
+/// extension, ignoring case
+pub fn pair_by_stem(music: &[PathBuf], images: &[PathBuf]) -> Vec<ClipSave> {
+    let images: HashMap<String, &PathBuf> = images
+        .iter()
+        .filter_map(|image| Some((stem_key(image)?, image)))
+        .collect();
+
+    music
+        .iter()
+        .filter_map(|music| {
+            Some(ClipSave {
+                title: title_from_path(music)?,
+                image_path: stem_key(music)
+                    .and_then(|stem| images.get(&stem))
+                    .map(|&image| image.clone()),
+                music_path: music.clone(),
+                offset: Duration::default(),
+                video_path: None,
+                reveal_effect: None,
+                challenge: None,
+                music_hash: None,
+                artist: None,
+                album: None,
+            })
+        })
+        .collect()
+}
+
+/// Writes an image extracted from a media in `dir`, named after its content so that the covers
+/// shared by the songs of an album are stored once
+pub fn store_cover(dir: &Path, data: &[u8], extension: &str) -> Result<PathBuf, Error> {
+    let name = blake3::hash(data).to_hex();
+    let path = dir.join(format!("cover-{}.{}", &name[..16], extension));
+    if !path.exists() {
+        fs::create_dir_all(dir)?;
+        fs::write(&path, data)?;
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn store_cover_once() {
+        let dir = std::env::temp_dir().join(format!("bt-save-covers-{}", std::process::id()));
+        let first = store_cover(&dir, b"cover", "png").unwrap();
+        let second = store_cover(&dir, b"cover", "png").unwrap();
+        let other = store_cover(&dir, b"other cover", "jpg").unwrap();
+        let stored = fs::read(&first).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+        assert_eq!(stored, b"cover");
+    }
+
+    #[test]
+    fn pair_music_and_images() {
+        let music: Vec<PathBuf> = vec![
+            "/music/Daft_Punk - One More Time.mp3".into(),
+            "/music/intro.ogg".into(),
+        ];
+        let images: Vec<PathBuf> = vec![
+            "/images/intro.PNG".into(),
+            "/images/daft_punk - one more time.jpg".into(),
+            "/images/unused.png".into(),
+        ];
+
+        let clips = pair_by_stem(&music, &images);
+        let clips: Vec<_> = clips
+            .iter()
+            .map(|clip| (clip.title.as_str(), clip.image_path.as_deref()))
+            .collect();
+        assert_eq!(
+            clips,
+            vec![
+                (
+                    "Daft Punk - One More Time",
+                    Some(Path::new("/images/daft_punk - one more time.jpg"))
+                ),
+                ("intro", Some(Path::new("/images/intro.PNG"))),
+            ]
+        );
+    }
+}
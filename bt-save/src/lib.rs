@@ -1,26 +1,154 @@
 use serde::{Deserialize, Serialize};
 use std::{
+    convert::TryFrom,
+    fmt::{self, Display},
     fs::File,
     io::{BufReader, Cursor, Read},
     path::{Path, PathBuf},
     time::Duration,
 };
 
+pub mod import;
+pub mod playlist;
+pub mod recovery;
+pub mod relink;
+pub mod session;
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("could not read the save file")]
     Serde(#[from] serde_json::Error),
     #[error("an I/O error occured")]
     Io(#[from] std::io::Error),
+    #[error("invalid playlist: {0}")]
+    Playlist(String),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ClipSave {
     pub title: String,
-    pub image_path: PathBuf,
+    /// When missing a visual is generated from the music for the reveal
+    pub image_path: Option<PathBuf>,
+    /// Audio source of the clip, can be a video file in which case its audio track is used
     pub music_path: PathBuf,
     #[serde(default)]
     pub offset: Duration,
+    /// Video shown instead of the image for the reveal, synchronized with the music
+    #[serde(default)]
+    pub video_path: Option<PathBuf>,
+    /// Overrides the reveal effect of the settings
+    #[serde(default)]
+    pub reveal_effect: Option<RevealEffect>,
+    /// Overrides the challenge of the settings
+    #[serde(default)]
+    pub challenge: Option<Challenge>,
+    /// Content hash of the music, to find it again if it was moved or renamed
+    #[serde(default)]
+    pub music_hash: Option<String>,
+    /// Read from the tags of the music when the clip is added
+    #[serde(default)]
+    pub artist: Option<String>,
+    #[serde(default)]
+    pub album: Option<String>,
+}
+
+/// Alteration of the music of a clip to make it harder to guess
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "snake_case", try_from = "UncheckedChallenge")]
+pub enum Challenge {
+    /// The music is played as is
+    #[default]
+    Normal,
+    /// The music is played backwards
+    Reversed,
+    /// The music is played faster, keeping its pitch
+    SpedUp { percent: u32 },
+    /// Only the first seconds of the music are played
+    IntroOnly { seconds: u32 },
+}
+
+impl Challenge {
+    pub const PRESETS: [Challenge; 4] = [
+        Self::Normal,
+        Self::Reversed,
+        Self::SpedUp { percent: 150 },
+        Self::IntroOnly { seconds: 2 },
+    ];
+
+    /// Checks the parameters of the challenge, a speed of zero would never play anything
+    pub fn validate(self) -> Result<Self, String> {
+        match self {
+            Self::SpedUp { percent: 0 } => {
+                Err("the speed of a sped up challenge can't be 0%".into())
+            }
+            challenge => Ok(challenge),
+        }
+    }
+}
+
+/// A challenge as written in the file, before it is validated
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum UncheckedChallenge {
+    Normal,
+    Reversed,
+    SpedUp { percent: u32 },
+    IntroOnly { seconds: u32 },
+}
+
+impl TryFrom<UncheckedChallenge> for Challenge {
+    type Error = String;
+
+    fn try_from(challenge: UncheckedChallenge) -> Result<Self, Self::Error> {
+        match challenge {
+            UncheckedChallenge::Normal => Self::Normal,
+            UncheckedChallenge::Reversed => Self::Reversed,
+            UncheckedChallenge::SpedUp { percent } => Self::SpedUp { percent },
+            UncheckedChallenge::IntroOnly { seconds } => Self::IntroOnly { seconds },
+        }
+        .validate()
+    }
+}
+
+impl Display for Challenge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Normal => write!(f, "Normal"),
+            Self::Reversed => write!(f, "Reversed"),
+            Self::SpedUp { percent } => write!(f, "Sped up ({}%)", percent),
+            Self::IntroOnly { seconds } => write!(f, "Intro only ({}s)", seconds),
+        }
+    }
+}
+
+/// How the image of a clip is shown during the countdown
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RevealEffect {
+    /// The image only appears after the countdown
+    #[default]
+    Hidden,
+    /// The image is blurred and gets sharper as the countdown runs out
+    Blur,
+    /// The image is pixelated and gets sharper as the countdown runs out
+    Pixelate,
+    /// The image is zoomed in and zooms out as the countdown runs out
+    Zoom,
+}
+
+impl RevealEffect {
+    pub const ALL: [RevealEffect; 4] = [Self::Hidden, Self::Blur, Self::Pixelate, Self::Zoom];
+}
+
+impl Display for RevealEffect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hidden => write!(f, "Hidden"),
+            Self::Blur => write!(f, "Blur"),
+            Self::Pixelate => write!(f, "Pixelate"),
+            Self::Zoom => write!(f, "Zoom"),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -34,6 +162,10 @@ pub struct SaveFile {
 pub struct Settings {
     pub duration: u32,
     pub countdown: Option<PathBuf>,
+    #[serde(default)]
+    pub reveal_effect: RevealEffect,
+    #[serde(default)]
+    pub challenge: Challenge,
 }
 
 #[inline]
@@ -60,3 +192,16 @@ impl SaveFile {
         Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_stopped_speed() {
+        let challenge: Challenge = serde_json::from_str(r#"{"sped_up":{"percent":150}}"#).unwrap();
+        assert_eq!(challenge, Challenge::SpedUp { percent: 150 });
+        assert!(serde_json::from_str::<Challenge>(r#"{"sped_up":{"percent":0}}"#).is_err());
+        assert!(serde_json::from_str::<Challenge>(r#""reversed""#).is_ok());
+    }
+}
@@ -0,0 +1,435 @@
+use crate::{import::title_from_path, ClipSave, Error};
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Formats of the playlists that can be imported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaylistFormat {
+    M3u,
+    Xspf,
+    Csv,
+}
+
+impl PlaylistFormat {
+    pub const EXTENSIONS: &'static [&'static str] = &["m3u", "m3u8", "xspf", "csv"];
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "m3u" | "m3u8" => Some(Self::M3u),
+            "xspf" => Some(Self::Xspf),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// A song of a playlist
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    /// Relative paths are resolved from the folder of the playlist
+    pub path: PathBuf,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub offset: Duration,
+    pub image: Option<PathBuf>,
+}
+
+impl Entry {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            title: None,
+            artist: None,
+            offset: Duration::default(),
+            image: None,
+        }
+    }
+}
+
+/// Clips made from a playlist, with the timeline in the order of the playlist
+#[derive(Debug, Clone, Default)]
+pub struct Import {
+    pub clips: Vec<ClipSave>,
+    pub timeline: Vec<String>,
+    /// Entries whose file does not exist or is not local, they are left out
+    pub unresolved: Vec<PathBuf>,
+}
+
+/// Reads the entries of a playlist, its format being guessed from its extension
+pub fn read(path: &Path) -> Result<Vec<Entry>, Error> {
+    let format = PlaylistFormat::from_path(path)
+        .ok_or_else(|| Error::Playlist("unknown playlist extension".into()))?;
+    let text = fs::read_to_string(path)?;
+    let path = fs::canonicalize(path)?;
+    let base = path.parent().unwrap_or_else(|| Path::new("/"));
+    parse(format, &text, base)
+}
+
+pub fn parse(format: PlaylistFormat, text: &str, base: &Path) -> Result<Vec<Entry>, Error> {
+    let text = text.trim_start_matches('\u{feff}');
+    match format {
+        PlaylistFormat::M3u => Ok(parse_m3u(text, base)),
+        PlaylistFormat::Xspf => parse_xspf(text, base),
+        PlaylistFormat::Csv => parse_csv(text, base),
+    }
+}
+
+/// Makes a clip of each song, the songs found twice with the same offset sharing their clip.
+///
+/// The titles are made unique, among themselves and with the `existing` ones.
+pub fn resolve(entries: Vec<Entry>, existing: &HashSet<String>) -> Import {
+    let mut import = Import::default();
+    let mut titles = existing.clone();
+
+    for entry in entries {
+        if !entry.path.is_file() {
+            import.unresolved.push(entry.path);
+            continue;
+        }
+
+        if let Some(clip) = import.clips.iter().find(|clip| {
+            clip.music_path == entry.path
+                && clip.offset == entry.offset
+                && !matches!(&entry.title, Some(title) if *title != clip.title)
+        }) {
+            import.timeline.push(clip.title.clone());
+            continue;
+        }
+
+        let path = &entry.path;
+        let base_title = match entry.title.or_else(|| title_from_path(path)) {
+            Some(title) => title,
+            None => {
+                import.unresolved.push(entry.path);
+                continue;
+            }
+        };
+        let mut title = base_title.clone();
+        let mut index = 2;
+        while titles.contains(&title) {
+            title = format!("{} ({})", base_title, index);
+            index += 1;
+        }
+        titles.insert(title.clone());
+
+        import.timeline.push(title.clone());
+        import.clips.push(ClipSave {
+            title,
+            image_path: entry.image,
+            music_path: entry.path,
+            offset: entry.offset,
+            video_path: None,
+            reveal_effect: None,
+            challenge: None,
+            music_hash: None,
+            artist: entry.artist,
+            album: None,
+        });
+    }
+
+    import
+}
+
+/// Path of an entry, that can be a `file://` URL or relative to the playlist
+fn entry_path(location: &str, base: &Path) -> PathBuf {
+    let location = location.trim();
+    let path = match location.strip_prefix("file://") {
+        // `file://localhost/path` is the same as `file:///path`
+        Some(url) => PathBuf::from(percent_decode(url.strip_prefix("localhost").unwrap_or(url))),
+        None => PathBuf::from(location),
+    };
+
+    if path.is_relative() && !location.contains("://") {
+        base.join(path)
+    } else {
+        path
+    }
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = if bytes[i] == b'%' {
+            text.get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        } else {
+            None
+        };
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn parse_m3u(text: &str, base: &Path) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut title = None;
+
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            title = info
+                .split_once(',')
+                .map(|(_, title)| title.trim().to_string())
+                .filter(|title| !title.is_empty());
+        } else if !line.starts_with('#') {
+            let mut entry = Entry::new(entry_path(line, base));
+            entry.title = title.take();
+            entries.push(entry);
+        }
+    }
+
+    entries
+}
+
+fn xml_unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Text of the first `<tag>` element of the xml fragment
+fn xml_element<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}>", tag);
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find(&format!("</{}>", tag))?;
+    Some(&xml[start..end])
+}
+
+fn parse_xspf(text: &str, base: &Path) -> Result<Vec<Entry>, Error> {
+    let list = xml_element(text, "trackList")
+        .ok_or_else(|| Error::Playlist("the XSPF playlist has no trackList".into()))?;
+
+    let mut entries = Vec::new();
+    for track in list.split("<track>").skip(1) {
+        let track = track.split("</track>").next().unwrap_or(track);
+        let location = match xml_element(track, "location") {
+            Some(location) => xml_unescape(location),
+            None => continue,
+        };
+        let text = |tag| {
+            xml_element(track, tag)
+                .map(|text| xml_unescape(text).trim().to_string())
+                .filter(|text| !text.is_empty())
+        };
+
+        entries.push(Entry {
+            path: entry_path(&location, base),
+            title: text("title"),
+            artist: text("creator"),
+            offset: Duration::default(),
+            image: text("image").map(|image| entry_path(&image, base)),
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Splits a CSV line in fields, a field can be quoted to contain commas, with `""` for a quote
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+
+    fields.iter().map(|f| f.trim().to_string()).collect()
+}
+
+/// Parses an offset in seconds, or as `minutes:seconds`
+fn parse_offset(offset: &str) -> Option<Duration> {
+    if offset.is_empty() {
+        return Some(Duration::default());
+    }
+
+    let (minutes, seconds) = match offset.split_once(':') {
+        Some((minutes, seconds)) => (minutes.parse::<u64>().ok()?, seconds),
+        None => (0, offset),
+    };
+    let seconds: f64 = seconds.parse().ok()?;
+    if !seconds.is_finite() || seconds < 0. {
+        return None;
+    }
+    Some(Duration::from_secs(minutes * 60) + Duration::from_secs_f64(seconds))
+}
+
+fn parse_csv(text: &str, base: &Path) -> Result<Vec<Entry>, Error> {
+    let mut entries = Vec::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let fields = csv_fields(line);
+        let field = |index: usize| {
+            fields
+                .get(index)
+                .map(String::as_str)
+                .filter(|f| !f.is_empty())
+        };
+
+        let path = match field(0) {
+            None => continue,
+            Some(header) if number == 0 && header.eq_ignore_ascii_case("path") => continue,
+            Some(path) => path,
+        };
+        let offset = parse_offset(field(2).unwrap_or(""))
+            .ok_or_else(|| Error::Playlist(format!("invalid offset on line {}", number + 1)))?;
+
+        entries.push(Entry {
+            path: entry_path(path, base),
+            title: field(1).map(ToOwned::to_owned),
+            artist: None,
+            offset,
+            image: field(3).map(|image| entry_path(image, base)),
+        });
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_m3u_playlist() {
+        let entries = parse(
+            PlaylistFormat::M3u,
+            "\u{feff}#EXTM3U\n#EXTINF:183,Artist - Song\nmusic/song.mp3\n\n/abs/other.ogg\nfile:///abs/with%20space.flac\n",
+            Path::new("/lists"),
+        )
+        .unwrap();
+
+        let entries: Vec<_> = entries
+            .iter()
+            .map(|e| (e.path.to_str().unwrap(), e.title.as_deref()))
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                ("/lists/music/song.mp3", Some("Artist - Song")),
+                ("/abs/other.ogg", None),
+                ("/abs/with space.flac", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_xspf_playlist() {
+        let entries = parse(
+            PlaylistFormat::Xspf,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<playlist version="1" xmlns="http://xspf.org/ns/0/">
+  <trackList>
+    <track>
+      <location>file:///music/Rock%20%26%20Roll.mp3</location>
+      <title>Rock &amp; Roll</title>
+      <creator>Band</creator>
+      <image>covers/rock.png</image>
+    </track>
+    <track><location>song.ogg</location></track>
+  </trackList>
+</playlist>"#,
+            Path::new("/lists"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            entries,
+            vec![
+                Entry {
+                    path: "/music/Rock & Roll.mp3".into(),
+                    title: Some("Rock & Roll".into()),
+                    artist: Some("Band".into()),
+                    offset: Duration::default(),
+                    image: Some("/lists/covers/rock.png".into()),
+                },
+                Entry::new("/lists/song.ogg".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_csv_playlist() {
+        let entries = parse(
+            PlaylistFormat::Csv,
+            "path,title,offset,image\nsong.mp3,\"Hello, \"\"World\"\"\",1:30,cover.png\n/other.mp3,,12.5,\n",
+            Path::new("/lists"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            entries,
+            vec![
+                Entry {
+                    path: "/lists/song.mp3".into(),
+                    title: Some("Hello, \"World\"".into()),
+                    artist: None,
+                    offset: Duration::from_secs(90),
+                    image: Some("/lists/cover.png".into()),
+                },
+                Entry {
+                    offset: Duration::from_secs_f64(12.5),
+                    ..Entry::new("/other.mp3".into())
+                },
+            ]
+        );
+        assert!(parse(PlaylistFormat::Csv, "song.mp3,,soon", Path::new("/")).is_err());
+    }
+
+    #[test]
+    fn resolve_entries() {
+        let dir = std::env::temp_dir().join(format!("bt-save-playlist-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("song.mp3"), b"song").unwrap();
+        fs::write(dir.join("intro.mp3"), b"intro").unwrap();
+
+        let entries = vec![
+            Entry::new(dir.join("song.mp3")),
+            Entry::new(dir.join("missing.mp3")),
+            Entry::new(dir.join("intro.mp3")),
+            Entry::new(dir.join("song.mp3")),
+            Entry {
+                offset: Duration::from_secs(60),
+                ..Entry::new(dir.join("song.mp3"))
+            },
+        ];
+        let existing = std::iter::once("intro".to_string()).collect();
+        let import = resolve(entries, &existing);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let titles: Vec<_> = import.clips.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, vec!["song", "intro (2)", "song (2)"]);
+        assert_eq!(
+            import.timeline,
+            vec!["song", "intro (2)", "song", "song (2)"]
+        );
+        assert_eq!(import.unresolved, vec![dir.join("missing.mp3")]);
+    }
+}
@@ -0,0 +1,65 @@
+use crate::{Error, SaveFile};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+/// A snapshot of a project with unsaved changes, to restore it after a crash
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Recovery {
+    /// Where the project was saved, if it ever was
+    pub project: Option<PathBuf>,
+    pub save: SaveFile,
+}
+
+impl Recovery {
+    /// Writes the snapshot to a temporary file first, so that a crash while writing can't
+    /// corrupt the previous one
+    pub fn store<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("tmp");
+        serde_json::to_writer(File::create(&tmp)?, self)?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Settings;
+
+    #[test]
+    fn store_and_load() {
+        let path =
+            std::env::temp_dir().join(format!("bt-save-recovery-{}.json", std::process::id()));
+        let recovery = Recovery {
+            project: Some("/projects/quiz.bt".into()),
+            save: SaveFile {
+                clips: Vec::new(),
+                timeline: vec![None, Some("clip".into())],
+                settings: Settings {
+                    duration: 20,
+                    countdown: None,
+                    reveal_effect: Default::default(),
+                    challenge: Default::default(),
+                },
+            },
+        };
+
+        recovery.store(&path).unwrap();
+        let loaded = Recovery::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.project, recovery.project);
+        assert_eq!(loaded.save.timeline, recovery.save.timeline);
+        assert_eq!(loaded.save.settings.duration, 20);
+        assert!(!path.with_extension("tmp").exists());
+    }
+}
@@ -0,0 +1,151 @@
+use crate::{ClipSave, Error};
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+};
+
+/// A file referenced by a clip
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MediaKind {
+    Music,
+    Image,
+    Video,
+}
+
+impl Display for MediaKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Music => write!(f, "Music"),
+            Self::Image => write!(f, "Image"),
+            Self::Video => write!(f, "Video"),
+        }
+    }
+}
+
+impl ClipSave {
+    pub fn media_path(&self, kind: MediaKind) -> Option<&Path> {
+        match kind {
+            MediaKind::Music => Some(&self.music_path),
+            MediaKind::Image => self.image_path.as_deref(),
+            MediaKind::Video => self.video_path.as_deref(),
+        }
+    }
+
+    pub fn set_media_path(&mut self, kind: MediaKind, path: PathBuf) {
+        match kind {
+            MediaKind::Music => self.music_path = path,
+            MediaKind::Image => self.image_path = Some(path),
+            MediaKind::Video => self.video_path = Some(path),
+        }
+    }
+
+    /// The files referenced by the clip that do not exist anymore
+    pub fn missing_media(&self) -> Vec<MediaKind> {
+        [MediaKind::Music, MediaKind::Image, MediaKind::Video]
+            .iter()
+            .copied()
+            .filter(|&kind| matches!(self.media_path(kind), Some(path) if !path.exists()))
+            .collect()
+    }
+}
+
+/// Hash of the content of a file, to recognize it after it was renamed
+pub fn content_hash(path: &Path) -> Result<String, Error> {
+    let mut hasher = blake3::Hasher::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// A missing file to look for
+#[derive(Debug, Clone)]
+pub struct Wanted {
+    pub path: PathBuf,
+    pub hash: Option<String>,
+}
+
+/// Looks for the wanted files in `folder` and its subfolders, returning the candidates in the
+/// same order.
+///
+/// A file with the same name is preferred, else the files with the same extension are hashed
+/// to find one with the same content.
+pub fn search(folder: &Path, wanted: &[Wanted]) -> Vec<Option<PathBuf>> {
+    let files: Vec<PathBuf> = walkdir::WalkDir::new(folder)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .map(walkdir::DirEntry::into_path)
+        .collect();
+
+    let mut hashes: HashMap<&Path, Option<String>> = HashMap::new();
+    wanted
+        .iter()
+        .map(|wanted| {
+            if let Some(found) = files
+                .iter()
+                .find(|file| file.file_name() == wanted.path.file_name())
+            {
+                return Some(found.clone());
+            }
+
+            let hash = wanted.hash.as_ref()?;
+            files
+                .iter()
+                .filter(|file| file.extension() == wanted.path.extension())
+                .find(|file| {
+                    hashes
+                        .entry(file.as_path())
+                        .or_insert_with(|| content_hash(file).ok())
+                        .as_ref()
+                        == Some(hash)
+                })
+                .cloned()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn search_by_name_and_hash() {
+        let folder = std::env::temp_dir().join(format!("bt-save-relink-{}", std::process::id()));
+        fs::create_dir_all(folder.join("a")).unwrap();
+        fs::create_dir_all(folder.join("b")).unwrap();
+        fs::write(folder.join("a/song.mp3"), b"song").unwrap();
+        fs::write(folder.join("b/renamed.mp3"), b"other song").unwrap();
+        fs::write(folder.join("b/renamed.ogg"), b"other song").unwrap();
+
+        let found = search(
+            &folder,
+            &[
+                Wanted {
+                    path: "/old/song.mp3".into(),
+                    hash: None,
+                },
+                Wanted {
+                    path: "/old/other.mp3".into(),
+                    hash: Some(content_hash(&folder.join("b/renamed.mp3")).unwrap()),
+                },
+                Wanted {
+                    path: "/old/unknown.mp3".into(),
+                    hash: None,
+                },
+            ],
+        );
+        fs::remove_dir_all(&folder).unwrap();
+
+        assert_eq!(
+            found,
+            vec![
+                Some(folder.join("a/song.mp3")),
+                Some(folder.join("b/renamed.mp3")),
+                None
+            ]
+        );
+    }
+}
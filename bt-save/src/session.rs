@@ -0,0 +1,220 @@
+use crate::Error;
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    fs::{self, File},
+    io::{BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+/// Points given for finding a clip
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scoring {
+    pub title: u32,
+    pub artist: u32,
+}
+
+impl Default for Scoring {
+    fn default() -> Self {
+        Self {
+            title: 2,
+            artist: 1,
+        }
+    }
+}
+
+/// What a team found on a round
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Answer {
+    pub team: String,
+    /// Index of the clip in the played timeline
+    pub round: usize,
+    /// Kept to make the file readable, the round identifies the clip
+    pub clip: String,
+    pub title: bool,
+    pub artist: bool,
+}
+
+/// The teams of a live blind test and their answers, stored next to the project
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Session {
+    pub teams: Vec<String>,
+    pub scoring: Scoring,
+    pub answers: Vec<Answer>,
+}
+
+/// Score of a team
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Standing {
+    /// Starts at 1, tied teams share the same rank
+    pub rank: usize,
+    pub team: String,
+    pub points: u32,
+    /// Number of titles found
+    pub titles: usize,
+    /// Number of artists found
+    pub artists: usize,
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+impl Session {
+    /// The session of the project saved at `project`, `quiz.bt` has `quiz.session`
+    pub fn path_for(project: &Path) -> PathBuf {
+        project.with_extension("session")
+    }
+
+    pub fn store<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        Ok(serde_json::to_writer_pretty(File::create(path)?, self)?)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+    }
+
+    pub fn add_team(&mut self, team: String) -> bool {
+        if self.teams.contains(&team) {
+            false
+        } else {
+            self.teams.push(team);
+            true
+        }
+    }
+
+    /// Removes a team along with its answers
+    pub fn remove_team(&mut self, team: &str) {
+        self.teams.retain(|t| t != team);
+        self.answers.retain(|answer| answer.team != team);
+    }
+
+    pub fn answer(&self, team: &str, round: usize) -> Option<&Answer> {
+        self.answers
+            .iter()
+            .find(|answer| answer.team == team && answer.round == round)
+    }
+
+    /// Records what a team found on a round, replacing its previous answer
+    pub fn set_answer(&mut self, answer: Answer) {
+        self.answers
+            .retain(|a| a.team != answer.team || a.round != answer.round);
+        if answer.title || answer.artist {
+            self.answers.push(answer);
+        }
+    }
+
+    /// The teams from the highest score to the lowest, ties keeping the order of the teams
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings: Vec<_> = self
+            .teams
+            .iter()
+            .map(|team| {
+                let answers = self.answers.iter().filter(|answer| answer.team == *team);
+                let titles = answers.clone().filter(|answer| answer.title).count();
+                let artists = answers.filter(|answer| answer.artist).count();
+                Standing {
+                    rank: 0,
+                    team: team.clone(),
+                    points: titles as u32 * self.scoring.title
+                        + artists as u32 * self.scoring.artist,
+                    titles,
+                    artists,
+                }
+            })
+            .collect();
+        standings.sort_by_key(|standing| Reverse(standing.points));
+
+        let mut previous = None;
+        for (index, standing) in standings.iter_mut().enumerate() {
+            standing.rank = match previous {
+                Some((rank, points)) if points == standing.points => rank,
+                _ => index + 1,
+            };
+            previous = Some((standing.rank, standing.points));
+        }
+        standings
+    }
+
+    /// Writes the standings as CSV, with a header line
+    pub fn write_standings<W: Write>(&self, mut writer: W) -> Result<(), Error> {
+        writeln!(writer, "rank,team,points,titles,artists")?;
+
+        for standing in self.standings() {
+            writeln!(
+                writer,
+                "{},{},{},{},{}",
+                standing.rank,
+                csv_field(&standing.team),
+                standing.points,
+                standing.titles,
+                standing.artists
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn export_standings<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let mut data = Vec::new();
+        self.write_standings(&mut data)?;
+        fs::write(path, data)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answer(team: &str, round: usize, title: bool, artist: bool) -> Answer {
+        Answer {
+            team: team.into(),
+            round,
+            clip: format!("clip {}", round),
+            title,
+            artist,
+        }
+    }
+
+    #[test]
+    fn standings_csv() {
+        let mut session = Session::default();
+        for team in &["Red", "Blue, the best", "Green"] {
+            assert!(session.add_team(team.to_string()));
+        }
+        assert!(!session.add_team("Red".into()));
+
+        session.set_answer(answer("Red", 0, true, false));
+        session.set_answer(answer("Blue, the best", 0, true, true));
+        session.set_answer(answer("Green", 1, false, true));
+        session.set_answer(answer("Red", 1, false, true));
+        // Answering again replaces the answer, and answering nothing removes it
+        session.set_answer(answer("Green", 1, true, true));
+        session.set_answer(answer("Green", 1, false, false));
+
+        let mut csv = Vec::new();
+        session.write_standings(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "rank,team,points,titles,artists\n\
+             1,Red,3,1,1\n\
+             1,\"Blue, the best\",3,1,1\n\
+             3,Green,0,0,0\n"
+        );
+        // The scoreboard of the presentation shows the same ranks
+        let ranks: Vec<_> = session.standings().iter().map(|s| s.rank).collect();
+        assert_eq!(ranks, [1, 1, 3]);
+
+        session.remove_team("Red");
+        assert_eq!(session.answers.len(), 1);
+        assert_eq!(
+            session.answer("Blue, the best", 0).map(|a| a.title),
+            Some(true)
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use iced_native::{
+    event, layout, mouse, overlay, window, Clipboard, Element, Event, Hasher, Layout, Length,
+    Point, Rectangle, Widget,
+};
+use std::{hash::Hash, path::PathBuf};
+
+/// Reports the presses of the left mouse button that its content ignores, and the releases,
+/// so that the content can be dragged around. Files dropped from the file manager over it can
+/// be reported too.
+pub(crate) struct DragArea<'a, Message, Renderer> {
+    content: Element<'a, Message, Renderer>,
+    on_press: Option<Message>,
+    on_release: Option<Message>,
+    /// Releases outside of the area are reported too
+    release_anywhere: bool,
+    on_file_drop: Option<Box<dyn Fn(PathBuf) -> Message + 'a>>,
+}
+
+impl<'a, Message, Renderer> DragArea<'a, Message, Renderer> {
+    pub(crate) fn new(content: impl Into<Element<'a, Message, Renderer>>) -> Self {
+        Self {
+            content: content.into(),
+            on_press: None,
+            on_release: None,
+            release_anywhere: false,
+            on_file_drop: None,
+        }
+    }
+
+    pub(crate) fn on_press(mut self, message: Message) -> Self {
+        self.on_press = Some(message);
+        self
+    }
+
+    pub(crate) fn on_release(mut self, message: Message) -> Self {
+        self.on_release = Some(message);
+        self
+    }
+
+    /// The message is sent wherever the button is released, after the ones of the content
+    pub(crate) fn on_release_anywhere(mut self, message: Message) -> Self {
+        self.on_release = Some(message);
+        self.release_anywhere = true;
+        self
+    }
+
+    /// The dropped files are not seen by the application-wide subscriptions anymore
+    pub(crate) fn on_file_drop(mut self, f: impl Fn(PathBuf) -> Message + 'a) -> Self {
+        self.on_file_drop = Some(Box::new(f));
+        self
+    }
+}
+
+impl<'a, Message, Renderer> Widget<Message, Renderer> for DragArea<'a, Message, Renderer>
+where
+    Message: Clone,
+    Renderer: iced_native::Renderer,
+{
+    fn width(&self) -> Length {
+        self.content.width()
+    }
+
+    fn height(&self) -> Length {
+        self.content.height()
+    }
+
+    fn layout(&self, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        self.content.layout(renderer, limits)
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut Renderer,
+        defaults: &Renderer::Defaults,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+    ) -> Renderer::Output {
+        self.content
+            .draw(renderer, defaults, layout, cursor_position, viewport)
+    }
+
+    fn hash_layout(&self, state: &mut Hasher) {
+        struct Marker;
+        std::any::TypeId::of::<Marker>().hash(state);
+
+        self.content.hash_layout(state);
+    }
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        messages: &mut Vec<Message>,
+    ) -> event::Status {
+        let status = self.content.on_event(
+            event.clone(),
+            layout,
+            cursor_position,
+            renderer,
+            clipboard,
+            messages,
+        );
+        let over = layout.bounds().contains(cursor_position);
+
+        match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
+                if over && status == event::Status::Ignored =>
+            {
+                if let Some(message) = &self.on_press {
+                    messages.push(message.clone());
+                    return event::Status::Captured;
+                }
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+                if over || self.release_anywhere =>
+            {
+                if let Some(message) = &self.on_release {
+                    messages.push(message.clone());
+                }
+            }
+            Event::Window(window::Event::FileDropped(path))
+                if over && status == event::Status::Ignored =>
+            {
+                if let Some(on_file_drop) = &self.on_file_drop {
+                    messages.push(on_file_drop(path));
+                    return event::Status::Captured;
+                }
+            }
+            _ => {}
+        }
+
+        status
+    }
+
+    fn overlay(&mut self, layout: Layout<'_>) -> Option<overlay::Element<'_, Message, Renderer>> {
+        self.content.overlay(layout)
+    }
+}
+
+impl<'a, Message, Renderer> From<DragArea<'a, Message, Renderer>> for Element<'a, Message, Renderer>
+where
+    Message: 'a + Clone,
+    Renderer: 'a + iced_native::Renderer,
+{
+    fn from(area: DragArea<'a, Message, Renderer>) -> Self {
+        Element::new(area)
+    }
+}
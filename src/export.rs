@@ -1,4 +1,8 @@
-use bt_export::{clip_duration_command, ffmpeg_command};
+use bt_export::{
+    audio_ffmpeg_command, ffmpeg_command, media_info, AudioCue, AudioFormat, Item,
+    DEFAULT_TICK_DURATION,
+};
+use bt_save::Settings;
 use iced_futures::futures;
 use std::{
     collections::HashMap,
@@ -15,10 +19,11 @@ use crate::Clip;
 
 #[derive(Clone)]
 pub(crate) struct Export {
-    countdown: PathBuf,
+    countdown: Option<PathBuf>,
     output: PathBuf,
-    items: Vec<(Duration, PathBuf, PathBuf)>,
+    items: Vec<Item>,
     duration: u32,
+    audio: Option<AudioFormat>,
 }
 
 impl Export {
@@ -26,29 +31,35 @@ impl Export {
         output: PathBuf,
         items: &[&str],
         clips: &HashMap<String, Clip>,
-        countdown: PathBuf,
-        duration: u32,
+        settings: Settings,
     ) -> Result<Self, String> {
+        let audio = AudioFormat::from_path(&output);
+        if audio.is_none() && settings.countdown.is_none() {
+            return Err("Countdown was not provided".into());
+        }
+
         let items = items
             .iter()
             .map(|&name| {
                 clips
                     .get(name)
                     .ok_or_else(|| "Clip does not exist".to_string())
-                    .map(|clip| {
-                        (
-                            clip.offset,
-                            clip.music_path.clone(),
-                            clip.image_path.clone(),
-                        )
+                    .map(|clip| Item {
+                        offset: clip.offset,
+                        music: clip.music_path.clone(),
+                        image: clip.image_path.clone(),
+                        video: clip.video_path.clone(),
+                        effect: clip.reveal_effect.unwrap_or(settings.reveal_effect),
+                        challenge: clip.challenge.unwrap_or(settings.challenge),
                     })
             })
             .collect::<Result<_, _>>()?;
         Ok(Self {
-            countdown,
+            countdown: settings.countdown,
             output,
-            duration,
+            duration: settings.duration,
             items,
+            audio,
         })
     }
 }
@@ -57,6 +68,7 @@ impl Export {
 pub(crate) enum Progress {
     Started,
     Frame(u64),
+    Time(Duration),
     Done,
     Error(String),
 }
@@ -103,20 +115,38 @@ where
             move |state| async move {
                 match state {
                     State::Ready(export) => {
-                        let countdown_duration = err_prop!(video_duration(&export.countdown).await);
+                        let countdown_duration = match &export.countdown {
+                            Some(countdown) => err_prop!(video_duration(countdown).await),
+                            None => DEFAULT_TICK_DURATION,
+                        };
                         if countdown_duration > export.duration {
                             return err("Countdown can't be longer than the duration".into());
                         }
 
                         eprintln!("Started ffmpeg");
 
-                        let mut ffmpeg_cmd = Command::from(ffmpeg_command(
-                            export.duration,
-                            countdown_duration,
-                            &export.countdown,
-                            &export.items,
-                            &export.output,
-                        ));
+                        let ffmpeg_cmd = match (export.audio, &export.countdown) {
+                            (Some(format), countdown) => audio_ffmpeg_command(
+                                export.duration,
+                                countdown_duration,
+                                match countdown {
+                                    Some(countdown) => AudioCue::Countdown(countdown),
+                                    None => AudioCue::Tick,
+                                },
+                                &export.items,
+                                format,
+                                &export.output,
+                            ),
+                            (None, Some(countdown)) => ffmpeg_command(
+                                export.duration,
+                                countdown_duration,
+                                countdown,
+                                &export.items,
+                                &export.output,
+                            ),
+                            (None, None) => unreachable!("checked in Export::new"),
+                        };
+                        let mut ffmpeg_cmd = Command::from(ffmpeg_cmd);
                         let child = err_prop!(ffmpeg_cmd
                             .spawn()
                             .map_err(|err| format!("error launching ffmpeg: {}", err)));
@@ -160,6 +190,18 @@ where
                                         ))
                                     }
                                 },
+                                "out_time_us" => match value.parse() {
+                                    Err(e) => {
+                                        eprintln!("Could not parse output time: {:?}", e);
+                                        continue;
+                                    }
+                                    Ok(v) => {
+                                        return Some((
+                                            Progress::Time(Duration::from_micros(v)),
+                                            State::Exporting { stdout },
+                                        ))
+                                    }
+                                },
                                 "progress" if value == "end" => {
                                     return Some((Progress::Done, State::Finished))
                                 }
@@ -175,14 +217,11 @@ where
 }
 
 async fn video_duration(countdown: &Path) -> Result<u32, String> {
-    let mut cmd = Command::from(clip_duration_command(countdown));
-    let countdown_command = cmd.output().await.map_err(|err| err.to_string())?;
-
-    let countdown_duration: f32 = String::from_utf8(countdown_command.stdout)
-        .expect("ffprobe should give utf8")
-        .trim_end()
-        .parse()
-        .expect("ffprobe should give a duration");
+    let countdown = countdown.to_owned();
+    let info = tokio::task::spawn_blocking(move || media_info::probe(&countdown))
+        .await
+        .map_err(|err| err.to_string())?
+        .map_err(|err| err.to_string())?;
 
-    Ok(countdown_duration as _)
+    Ok(info.duration.as_secs() as _)
 }
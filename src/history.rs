@@ -0,0 +1,177 @@
+use std::collections::VecDeque;
+
+use crate::{BlindTestBuilder, Clip};
+
+/// Number of edits that can be undone
+const HISTORY_DEPTH: usize = 100;
+
+/// A change to the project, applying it returns the edit reverting it
+pub(crate) enum Edit {
+    /// Adds a clip, and puts it back in the timeline entries in `slots`
+    InsertClip {
+        clip: Box<Clip>,
+        slots: Vec<usize>,
+    },
+    /// Removes a clip, emptying the timeline entries using it
+    RemoveClip(String),
+    /// Replaces the clip with the same title
+    ReplaceClip(Box<Clip>),
+    TimelineInsert {
+        index: usize,
+        clip: Option<String>,
+    },
+    TimelineRemove(usize),
+    TimelineSwap(usize, usize),
+    /// The entry at `i` becomes the one that was at `order[i]`
+    TimelineReorder(Vec<usize>),
+    TimelineSet {
+        index: usize,
+        clip: Option<String>,
+    },
+    Settings(Box<bt_save::Settings>),
+    /// Edits applied in order, as one step of the history
+    Batch(Vec<Edit>),
+}
+
+impl Edit {
+    pub(crate) fn apply(self, app: &mut BlindTestBuilder) -> Edit {
+        match self {
+            Edit::InsertClip { clip, slots } => {
+                let title = clip.title.clone();
+                app.clips.insert(title.clone(), *clip);
+                for index in slots {
+                    app.timeline.set(index, Some(title.clone()));
+                }
+                Edit::RemoveClip(title)
+            }
+            // A stale edit is skipped, its inverse doing nothing
+            Edit::RemoveClip(title) => match app.clips.remove(&title) {
+                Some(clip) => {
+                    let slots = app.timeline.clear_clip(&title);
+                    Edit::InsertClip {
+                        clip: Box::new(clip),
+                        slots,
+                    }
+                }
+                None => {
+                    eprintln!("Tried to remove non existent clip {}", title);
+                    Edit::Batch(Vec::new())
+                }
+            },
+            Edit::ReplaceClip(clip) => match app.clips.get_mut(&clip.title) {
+                Some(old) => Edit::ReplaceClip(Box::new(std::mem::replace(old, *clip))),
+                None => {
+                    eprintln!("Tried to modify non existent clip {}", clip.title);
+                    Edit::Batch(Vec::new())
+                }
+            },
+            Edit::TimelineInsert { index, clip } => {
+                app.timeline.insert(index, clip, &app.stream_handle);
+                Edit::TimelineRemove(index)
+            }
+            Edit::TimelineRemove(index) => Edit::TimelineInsert {
+                index,
+                clip: app.timeline.remove(index),
+            },
+            Edit::TimelineSwap(a, b) => {
+                app.timeline.swap(a, b);
+                Edit::TimelineSwap(a, b)
+            }
+            Edit::TimelineReorder(order) => {
+                app.timeline.reorder(&order);
+                Edit::TimelineReorder(inverse_order(&order))
+            }
+            Edit::TimelineSet { index, clip } => Edit::TimelineSet {
+                index,
+                clip: app.timeline.set(index, clip),
+            },
+            Edit::Settings(settings) => {
+                let old = app.settings();
+                app.set_settings(*settings);
+                Edit::Settings(Box::new(old))
+            }
+            Edit::Batch(edits) => {
+                let mut inverse: Vec<_> = edits.into_iter().map(|edit| edit.apply(app)).collect();
+                inverse.reverse();
+                Edit::Batch(inverse)
+            }
+        }
+    }
+}
+
+/// The order putting back the entries reordered by `order`
+fn inverse_order(order: &[usize]) -> Vec<usize> {
+    let mut inverse = vec![0; order.len()];
+    for (new, &old) in order.iter().enumerate() {
+        inverse[old] = new;
+    }
+    inverse
+}
+
+/// Edits that can be undone and redone, as the edits reverting them
+#[derive(Default)]
+pub(crate) struct History {
+    undo: VecDeque<Edit>,
+    redo: Vec<Edit>,
+}
+
+impl History {
+    /// Records the inverse of an edit that was just made
+    pub(crate) fn record(&mut self, inverse: Edit) {
+        self.redo.clear();
+        self.push_undo(inverse);
+    }
+
+    pub(crate) fn push_undo(&mut self, inverse: Edit) {
+        self.undo.push_back(inverse);
+        if self.undo.len() > HISTORY_DEPTH {
+            self.undo.pop_front();
+        }
+    }
+
+    pub(crate) fn pop_undo(&mut self) -> Option<Edit> {
+        self.undo.pop_back()
+    }
+
+    pub(crate) fn push_redo(&mut self, inverse: Edit) {
+        self.redo.push(inverse);
+    }
+
+    pub(crate) fn pop_redo(&mut self) -> Option<Edit> {
+        self.redo.pop()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    pub(crate) fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub(crate) fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timeline::reordered;
+
+    #[test]
+    fn undo_reorder() {
+        let entries: Vec<_> = (0..6).map(|index| format!("clip {}", index)).collect();
+        for order in &[
+            vec![2, 3, 4, 0, 1, 5],
+            vec![5, 0, 1, 2, 3, 4],
+            vec![1, 3, 5, 0, 2, 4],
+            vec![0, 1, 2, 3, 4, 5],
+        ] {
+            let moved: Vec<_> = reordered(entries.clone(), order);
+            let back: Vec<_> = reordered(moved, &inverse_order(order));
+            assert_eq!(back, entries);
+        }
+    }
+}
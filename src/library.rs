@@ -0,0 +1,281 @@
+use crate::{history::Edit, style, Clip, Message};
+use bt_save::ClipSave;
+use iced::{
+    button, image, pick_list, scrollable, text_input, Button, Column, Container, Element, Image,
+    Length, PickList, Row, Scrollable, Text, TextInput,
+};
+use itertools::Itertools;
+use std::{collections::HashMap, fmt};
+
+const THUMBNAIL_SIZE: u16 = 48;
+
+#[derive(Debug, Clone)]
+pub(crate) enum LibraryMessage {
+    Search(String),
+    Filter(LibraryFilter),
+    /// Appends the clip to the timeline
+    Add(String),
+}
+
+impl From<LibraryMessage> for Message {
+    fn from(m: LibraryMessage) -> Self {
+        Message::Library(m)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LibraryFilter {
+    All,
+    /// Clips that are in no entry of the timeline
+    Unused,
+    /// Clips that can't be used until their media are relinked
+    MissingMedia,
+}
+
+impl LibraryFilter {
+    const ALL: [LibraryFilter; 3] = [
+        LibraryFilter::All,
+        LibraryFilter::Unused,
+        LibraryFilter::MissingMedia,
+    ];
+}
+
+impl fmt::Display for LibraryFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::All => write!(f, "All clips"),
+            Self::Unused => write!(f, "Unused in timeline"),
+            Self::MissingMedia => write!(f, "Missing media"),
+        }
+    }
+}
+
+impl Default for LibraryFilter {
+    fn default() -> Self {
+        Self::All
+    }
+}
+
+/// A clip as listed in the library
+struct Item<'a> {
+    title: &'a str,
+    artist: Option<&'a str>,
+    album: Option<&'a str>,
+    image: Option<&'a image::Handle>,
+    /// The media that could not be found, the clip is not loaded
+    missing: Option<String>,
+    uses: usize,
+}
+
+impl Item<'_> {
+    /// Every word of the search is in the title, the artist, the album or the file name
+    fn matches(&self, search: &str, file_name: &str) -> bool {
+        let haystack = [Some(self.title), self.artist, self.album, Some(file_name)]
+            .iter()
+            .flatten()
+            .join("\n")
+            .to_lowercase();
+
+        search
+            .to_lowercase()
+            .split_whitespace()
+            .all(|word| haystack.contains(word))
+    }
+}
+
+#[derive(Default)]
+struct ItemState {
+    add_button: button::State,
+    edit_button: button::State,
+}
+
+/// Side panel listing all the clips of the project
+#[derive(Default)]
+pub(crate) struct Library {
+    search_input: text_input::State,
+    search: String,
+    filter_list: pick_list::State<LibraryFilter>,
+    filter: LibraryFilter,
+    scroll: scrollable::State,
+    items: Vec<ItemState>,
+}
+
+impl Library {
+    /// `timeline_len` is where the added clips go
+    pub(crate) fn update(&mut self, message: LibraryMessage, timeline_len: usize) -> Option<Edit> {
+        match message {
+            LibraryMessage::Search(search) => self.search = search,
+            LibraryMessage::Filter(filter) => self.filter = filter,
+            LibraryMessage::Add(title) => {
+                return Some(Edit::TimelineInsert {
+                    index: timeline_len,
+                    clip: Some(title),
+                })
+            }
+        }
+
+        None
+    }
+
+    /// `usage` is the number of entries of the timeline using each clip
+    pub(crate) fn view(
+        &mut self,
+        clips: &HashMap<String, Clip>,
+        missing: &[ClipSave],
+        usage: &HashMap<String, usize>,
+    ) -> Element<Message> {
+        let uses = |title: &str| usage.get(title).copied().unwrap_or(0);
+        let loaded = clips.values().map(|clip| {
+            let item = Item {
+                title: &clip.title,
+                artist: clip.artist.as_deref(),
+                album: clip.album.as_deref(),
+                image: clip.image.as_ref(),
+                missing: None,
+                uses: uses(&clip.title),
+            };
+            (item, &clip.music_path)
+        });
+        let unloaded = missing.iter().map(|clip| {
+            let item = Item {
+                title: &clip.title,
+                artist: clip.artist.as_deref(),
+                album: clip.album.as_deref(),
+                image: None,
+                missing: Some(clip.missing_media().iter().join(", ")),
+                uses: uses(&clip.title),
+            };
+            (item, &clip.music_path)
+        });
+
+        let filter = self.filter;
+        let search = self.search.trim();
+        let items: Vec<_> = loaded
+            .chain(unloaded)
+            .filter(|(item, _)| match filter {
+                LibraryFilter::All => true,
+                LibraryFilter::Unused => item.uses == 0,
+                LibraryFilter::MissingMedia => item.missing.is_some(),
+            })
+            .filter(|(item, music)| {
+                let file_name = music
+                    .file_name()
+                    .map(|name| name.to_string_lossy())
+                    .unwrap_or_default();
+                item.matches(search, &file_name)
+            })
+            .map(|(item, _)| item)
+            .sorted_by(|a, b| lexical_sort::natural_lexical_cmp(a.title, b.title))
+            .collect();
+
+        self.items.resize_with(items.len(), Default::default);
+        let mut list = Scrollable::new(&mut self.scroll)
+            .spacing(5)
+            .height(Length::Fill);
+        for (item, state) in items.iter().zip(&mut self.items) {
+            list = list.push(item_view(item, state));
+        }
+
+        let header = Row::new()
+            .spacing(5)
+            .align_items(iced::Align::Center)
+            .push(Text::new(format!("Library ({})", items.len())).size(24))
+            .push(PickList::new(
+                &mut self.filter_list,
+                LibraryFilter::ALL.to_vec(),
+                Some(self.filter),
+                |filter| LibraryMessage::Filter(filter).into(),
+            ));
+
+        Container::new(
+            Column::new()
+                .spacing(10)
+                .push(header)
+                .push(
+                    TextInput::new(
+                        &mut self.search_input,
+                        "Search title, artist, album or file",
+                        &self.search,
+                        |search| LibraryMessage::Search(search).into(),
+                    )
+                    .padding(5),
+                )
+                .push(list),
+        )
+        .width(Length::Units(340))
+        .height(Length::Fill)
+        .padding(10)
+        .style(style::BorderContainer)
+        .into()
+    }
+}
+
+fn item_view<'a>(item: &Item, state: &'a mut ItemState) -> Element<'a, Message> {
+    let thumbnail: Element<_> = match item.image {
+        Some(image) => Image::new(image.clone())
+            .width(Length::Units(THUMBNAIL_SIZE))
+            .height(Length::Units(THUMBNAIL_SIZE))
+            .into(),
+        None => Container::new(
+            Text::new(if item.missing.is_some() {
+                "Missing"
+            } else {
+                "Waveform"
+            })
+            .size(12),
+        )
+        .width(Length::Units(THUMBNAIL_SIZE))
+        .height(Length::Units(THUMBNAIL_SIZE))
+        .center_x()
+        .center_y()
+        .style(style::BorderContainer)
+        .into(),
+    };
+
+    let mut details = Column::new()
+        .spacing(2)
+        .width(Length::Fill)
+        .push(Text::new(item.title).size(18));
+    let metadata = item.artist.iter().chain(&item.album).join(" - ");
+    if !metadata.is_empty() {
+        details = details.push(Text::new(metadata).size(14));
+    }
+    details = details.push(
+        Text::new(match (&item.missing, item.uses) {
+            (Some(missing), _) => format!("Missing: {}", missing),
+            (None, 0) => "Not in the timeline".to_string(),
+            (None, 1) => "Used once".to_string(),
+            (None, uses) => format!("Used {} times", uses),
+        })
+        .size(14),
+    );
+
+    let mut row = Row::new()
+        .spacing(5)
+        .align_items(iced::Align::Center)
+        .push(thumbnail)
+        .push(details);
+    // Clips with missing media are not loaded, they are relinked from the header
+    if item.missing.is_none() {
+        row = row.push(
+            Column::new()
+                .spacing(2)
+                .push(
+                    Button::new(&mut state.add_button, Text::new("Add").size(14))
+                        .style(style::Button::Primary)
+                        .on_press(LibraryMessage::Add(item.title.to_owned()).into()),
+                )
+                .push(
+                    Button::new(&mut state.edit_button, Text::new("Edit").size(14))
+                        .style(style::Button::Primary)
+                        .on_press(Message::EditClip(item.title.to_owned())),
+                ),
+        );
+    }
+
+    Container::new(row)
+        .width(Length::Fill)
+        .padding(5)
+        .style(style::BorderContainer)
+        .into()
+}
@@ -0,0 +1,94 @@
+use iced_futures::futures;
+use std::{
+    collections::VecDeque,
+    hash::{Hash, Hasher},
+};
+
+use crate::Clip;
+
+/// Loads the clips of a save in the background, one at a time
+pub(crate) struct Loader {
+    generation: u64,
+    clips: VecDeque<bt_save::ClipSave>,
+}
+
+impl Loader {
+    /// The generation identifies the load, so that loading the same clips again restarts it
+    pub(crate) fn new(generation: u64, clips: Vec<bt_save::ClipSave>) -> Self {
+        Self {
+            generation,
+            clips: clips.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum LoadProgress {
+    Loaded(Box<Clip>),
+    /// Some files of the clip do not exist, it needs to be relinked
+    Missing(bt_save::ClipSave),
+    Failed {
+        title: String,
+        error: String,
+    },
+    Done,
+}
+
+enum State {
+    Loading(VecDeque<bt_save::ClipSave>),
+    Finished,
+}
+
+impl<H, I> iced_native::subscription::Recipe<H, I> for Loader
+where
+    H: Hasher,
+{
+    type Output = LoadProgress;
+
+    fn hash(&self, state: &mut H) {
+        struct Marker;
+        std::any::TypeId::of::<Marker>().hash(state);
+
+        self.generation.hash(state);
+    }
+
+    fn stream(
+        self: Box<Self>,
+        _input: futures::stream::BoxStream<'static, I>,
+    ) -> futures::stream::BoxStream<'static, Self::Output> {
+        Box::pin(futures::stream::unfold(
+            State::Loading(self.clips),
+            move |state| async move {
+                match state {
+                    State::Loading(mut clips) => {
+                        let clip = match clips.pop_front() {
+                            None => return Some((LoadProgress::Done, State::Finished)),
+                            Some(clip) => clip,
+                        };
+
+                        if !clip.missing_media().is_empty() {
+                            return Some((LoadProgress::Missing(clip), State::Loading(clips)));
+                        }
+
+                        let title = clip.title.clone();
+                        let progress =
+                            match tokio::task::spawn_blocking(move || Clip::load(clip)).await {
+                                Ok(Ok(clip)) => LoadProgress::Loaded(Box::new(clip)),
+                                Ok(Err(e)) => LoadProgress::Failed {
+                                    title,
+                                    error: format!("{:#}", e),
+                                },
+                                Err(e) => LoadProgress::Failed {
+                                    title,
+                                    error: format!("loading panicked: {}", e),
+                                },
+                            };
+
+                        Some((progress, State::Loading(clips)))
+                    }
+                    State::Finished => None,
+                }
+            },
+        ))
+    }
+}
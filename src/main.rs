@@ -1,23 +1,46 @@
+use bt_save::{recovery::Recovery, session::Session, Challenge, RevealEffect};
+use history::{Edit, History};
 use iced::{
-    button, executor, image, pick_list, window, Application, Button, Clipboard, Color, Column,
-    Command, Container, Element, Length, PickList, Row, Rule, Settings, Space, Subscription, Text,
+    button, executor, image, keyboard, pick_list, window, Application, Button, Clipboard, Color,
+    Column, Command, Container, Element, Image, Length, PickList, ProgressBar, Row, Rule, Settings,
+    Space, Subscription, Text,
 };
 use iced_aw::{modal, Modal};
 use itertools::Itertools;
-use modals::{ModalInnerState, ModalMessage};
-use rodio::{Decoder, OutputStream, OutputStreamHandle, Source};
+use library::{Library, LibraryMessage};
+use loader::{LoadProgress, Loader};
+use modals::{ClipBuilderMessage, DiscardAction, ModalInnerState, ModalMessage};
+use preferences::{Preferences, RecentProject};
+use presentation::{Presentation, PresentationMessage};
+use rodio::{buffer::SamplesBuffer, source::Zero, OutputStream, OutputStreamHandle, Source};
+use shortcuts::Shortcut;
 use std::{
-    collections::HashMap, io::Cursor, iter::FromIterator, path::PathBuf, sync::Arc, time::Duration,
+    collections::{HashMap, HashSet},
+    fmt,
+    iter::FromIterator,
+    path::{Path, PathBuf},
+    time::Duration,
 };
 
+mod drag;
 mod export;
+mod history;
+mod library;
+mod loader;
 mod modals;
+mod preferences;
+mod presentation;
+mod shortcuts;
+mod stream;
 mod timeline;
-
-type SoundSample = Box<[u8]>;
+mod waveform;
 
 fn main() -> iced::Result {
-    BlindTestBuilder::run(Settings::default())
+    BlindTestBuilder::run(Settings {
+        // Closing is confirmed first when there are unsaved changes
+        exit_on_close_request: false,
+        ..Settings::default()
+    })
 }
 
 use iced_wgpu as renderer;
@@ -26,14 +49,37 @@ use iced_winit as runtime;
 #[derive(Clone)]
 pub(crate) struct Clip {
     title: String,
-    music: Arc<SoundSample>,
-    image: image::Handle,
+    image: Option<image::Handle>,
     music_path: PathBuf,
-    image_path: PathBuf,
+    music_hash: Option<String>,
+    artist: Option<String>,
+    album: Option<String>,
+    image_path: Option<PathBuf>,
+    video_path: Option<PathBuf>,
+    reveal_effect: Option<RevealEffect>,
+    challenge: Option<Challenge>,
     offset: Duration,
     duration: Duration,
 }
 
+impl fmt::Debug for Clip {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The image data is left out, it would flood the output
+        f.debug_struct("Clip")
+            .field("title", &self.title)
+            .field("music_path", &self.music_path)
+            .field("artist", &self.artist)
+            .field("album", &self.album)
+            .field("image_path", &self.image_path)
+            .field("video_path", &self.video_path)
+            .field("reveal_effect", &self.reveal_effect)
+            .field("challenge", &self.challenge)
+            .field("offset", &self.offset)
+            .field("duration", &self.duration)
+            .finish()
+    }
+}
+
 impl Clip {
     fn save(&self) -> bt_save::ClipSave {
         bt_save::ClipSave {
@@ -41,52 +87,111 @@ impl Clip {
             music_path: self.music_path.clone(),
             image_path: self.image_path.clone(),
             offset: self.offset.clone(),
+            video_path: self.video_path.clone(),
+            reveal_effect: self.reveal_effect,
+            challenge: self.challenge,
+            music_hash: self.music_hash.clone(),
+            artist: self.artist.clone(),
+            album: self.album.clone(),
         }
     }
 
     fn load(clip: bt_save::ClipSave) -> anyhow::Result<Self> {
-        let music = Arc::new(std::fs::read(&clip.music_path)?.into_boxed_slice());
+        // Projects saved before hashes were stored get them on their next save
+        let music_hash = match clip.music_hash {
+            Some(hash) => Some(hash),
+            None => bt_save::relink::content_hash(&clip.music_path).ok(),
+        };
 
         Ok(Clip {
             title: clip.title.clone(),
-            image: (&clip.image_path).into(),
+            image: clip.image_path.as_ref().map(Into::into),
             image_path: clip.image_path,
-            music,
             music_path: clip.music_path,
+            music_hash,
+            artist: clip.artist,
+            album: clip.album,
+            video_path: clip.video_path,
+            reveal_effect: clip.reveal_effect,
+            challenge: clip.challenge,
             offset: clip.offset,
             duration: Default::default(),
         }
-        .fetch_duration())
-    }
-
-    pub(crate) fn fetch_duration(self) -> Self {
-        let cmd = std::process::Command::new("ffprobe")
-            .arg(&self.music_path)
-            .arg("-v")
-            .arg("quiet")
-            .arg("-show_entries")
-            .arg("format=duration")
-            .arg("-of")
-            .arg("csv=p=0")
-            .output()
-            .expect("ffprobe failed");
-        let dur: f64 = String::from_utf8(cmd.stdout)
-            .expect("ffprobe is utf8")
-            .trim_end()
-            .parse()
-            .expect("ffprobe is not an f64");
+        .fetch_duration()?)
+    }
 
-        Self {
-            duration: Duration::from_secs_f64(dur),
+    fn fetch_duration(self) -> anyhow::Result<Self> {
+        let info = bt_export::media_info::probe(&self.music_path)?;
+
+        Ok(Self {
+            duration: info.duration,
             ..self
+        })
+    }
+
+    /// The image of the clip, or a placeholder telling what the export reveals instead: its video
+    /// or a waveform generated from the music
+    pub(crate) fn image_view<'a>(&self) -> Element<'a, Message> {
+        let placeholder = match &self.video_path {
+            Some(video) => format!(
+                "Video reveal ({})",
+                video.file_name().unwrap_or_default().to_string_lossy()
+            ),
+            None => "No image (waveform)".to_string(),
+        };
+        match &self.image {
+            Some(image) => Image::new(image.clone()).into(),
+            None => Container::new(Text::new(placeholder))
+                .width(Length::Units(200))
+                .height(Length::Units(150))
+                .center_x()
+                .center_y()
+                .style(style::BorderContainer)
+                .into(),
         }
     }
 
-    fn audio(&self, duration: u32) -> Result<impl Source<Item = i16>, String> {
-        Ok(Decoder::new(Cursor::new(self.music.as_ref().clone()))
-            .map_err(|e| format!("Error reading music: {}", e))?
-            .skip_duration(self.offset)
-            .take_duration(Duration::from_secs(duration as u64)))
+    /// The music as heard by the players, `challenge` being used if the clip has none
+    fn audio(
+        &self,
+        duration: u32,
+        challenge: Challenge,
+    ) -> Result<Box<dyn Source<Item = i16> + Send>, String> {
+        let music = stream::open(&self.music_path, self.offset)?;
+        let duration = Duration::from_secs(duration as u64);
+
+        let audio: Box<dyn Source<Item = i16> + Send> = match self.challenge.unwrap_or(challenge) {
+            Challenge::Normal => Box::new(music.take_duration(duration)),
+            Challenge::Reversed => {
+                let music = music.take_duration(duration);
+                let (channels, sample_rate) = (music.channels(), music.sample_rate());
+                let samples: Vec<i16> = music.collect();
+                let reversed: Vec<i16> = samples
+                    .chunks(channels as usize)
+                    .rev()
+                    .flatten()
+                    .copied()
+                    .collect();
+                Box::new(SamplesBuffer::new(channels, sample_rate, reversed))
+            }
+            Challenge::SpedUp { percent } => {
+                let ratio = percent as f32 / 100.;
+                Box::new(stream::time_stretch(
+                    music.take_duration(duration.mul_f32(ratio)),
+                    ratio,
+                ))
+            }
+            Challenge::IntroOnly { seconds } => {
+                let intro = Duration::from_secs(seconds as u64).min(duration);
+                let silence = Zero::<i16>::new(music.channels(), music.sample_rate())
+                    .take_duration(duration - intro);
+                let parts: Vec<Box<dyn Source<Item = i16> + Send>> =
+                    vec![Box::new(music.take_duration(intro)), Box::new(silence)];
+                Box::new(rodio::source::from_iter(parts))
+            }
+        };
+
+        Ok(audio)
     }
 }
 
@@ -94,12 +199,18 @@ struct BlindTestBuilder {
     save: button::State,
     save_as: button::State,
     load: button::State,
+    recent: pick_list::State<RecentProject>,
     save_path: Option<PathBuf>,
+    /// The project was modified since it was last saved or loaded
+    dirty: bool,
+    exit: bool,
 
     choose_clip: pick_list::State<String>,
     choosen_clip: Option<String>,
     edit_clip: button::State,
     add_clip: button::State,
+    bulk_import: button::State,
+    import_playlist: button::State,
     global_settings: button::State,
     modal_state: modal::State<modals::ModalState>,
 
@@ -108,17 +219,90 @@ struct BlindTestBuilder {
 
     clips: HashMap<String, Clip>,
     timeline: timeline::Timeline,
-
+    library: Library,
+    /// The library panel is shown next to the timeline
+    library_open: bool,
+    library_button: button::State,
+    /// Replaces the builder in fullscreen while the blind test is run live
+    presentation: Option<Presentation>,
+    /// Teams and scores of the live blind test, stored next to the project
+    session: Session,
+    teams: button::State,
+    /// Lets a phone drive the timeline and the presentation while it is running
+    remote: Option<bt_remote::Remote>,
+    /// Where the phone reaches the remote
+    remote_address: String,
+    remote_button: button::State,
+    help: button::State,
+    /// Files dropped on the window, handled together once they are all received
+    dropped: Vec<PathBuf>,
+    /// Clip of the timeline entry the files were dropped on
+    drop_target: Option<String>,
+    loading: Option<Loading>,
+    load_generation: u64,
+    /// Clips referencing files that do not exist anymore, kept until they are relinked
+    missing: Vec<bt_save::ClipSave>,
+    relink: button::State,
+
+    history: History,
+    /// The clip as it was before its offset slider was dragged
+    offset_drag: Option<Box<Clip>>,
+    undo: button::State,
+    redo: button::State,
+
+    preferences: Preferences,
     music_dir: Option<PathBuf>,
     image_dir: Option<PathBuf>,
 
     clip_duration: u32,
     countdown: Option<PathBuf>,
+    reveal_effect: RevealEffect,
+    challenge: Challenge,
+}
+
+/// Clips being loaded in the background
+struct Loading {
+    generation: u64,
+    clips: Vec<bt_save::ClipSave>,
+    /// Restored once the clips are loaded, when loading a project
+    timeline: Option<Vec<Option<String>>>,
+    loaded: usize,
+    failures: Vec<(String, String)>,
+    /// Set when the clips come from a bulk import
+    import: Option<Import>,
+}
+
+/// Clips of a bulk import, recorded as one edit once they are loaded
+struct Import {
+    /// Appended to the timeline, the clips that failed to load are skipped
+    timeline: Vec<String>,
+    /// The clips that were loaded
+    titles: Vec<String>,
 }
 
 #[derive(Clone, Debug)]
 pub(crate) enum Message {
     AddClip,
+    BulkImport,
+    ImportPlaylist,
+    PlaylistPicked(Option<PathBuf>),
+    PlaylistRead(Result<bt_save::playlist::Import, String>),
+    ExportPlaylist,
+    ExportPlaylistTo(Option<PathBuf>),
+    Present,
+    Presentation(PresentationMessage),
+    Teams,
+    ToggleRemote,
+    RemotePoll,
+    Shortcut(Shortcut),
+    Help,
+    /// A file dropped from the file manager, on the entry of the timeline with this clip if any
+    FileDropped {
+        path: PathBuf,
+        clip: Option<String>,
+    },
+    DropFinished,
+    ArchiveOpened(Result<PathBuf, String>),
     ModalCancel,
     ModalClosed,
     PickedClip(String),
@@ -128,21 +312,49 @@ pub(crate) enum Message {
     SaveTo(Option<PathBuf>),
     LoadRequest,
     LoadFrom(Option<PathBuf>),
+    LoadRecent(RecentProject),
+    Loading(LoadProgress),
+    Relink,
+    Relinked(Vec<bt_save::ClipSave>),
+    Undo,
+    Redo,
+    Autosave,
+    Restore,
+    CloseRequested,
+    DiscardChanges(DiscardAction),
     SaveAs,
     Timeline(timeline::TimelineMessage),
+    Library(LibraryMessage),
+    ToggleLibrary,
     GlobalSettings,
-    EditClipOffset { clip: String, new_offset: u32 },
+    EditClipOffset {
+        clip: String,
+        new_offset: Duration,
+    },
+    ClipOffsetReleased,
+    EditClipEffect {
+        clip: String,
+        effect: Option<RevealEffect>,
+    },
+    EditClipChallenge {
+        clip: String,
+        challenge: Option<Challenge>,
+    },
 }
 
 impl BlindTestBuilder {
     fn new() -> Self {
         let (_output_stream, stream_handle) = OutputStream::try_default().unwrap();
+        let preferences = Preferences::load();
 
         Self {
             save: Default::default(),
             load: Default::default(),
+            recent: Default::default(),
             edit_clip: Default::default(),
             add_clip: Default::default(),
+            bulk_import: Default::default(),
+            import_playlist: Default::default(),
             global_settings: Default::default(),
             modal_state: modal::State::new(modals::ModalState::new()),
             choose_clip: Default::default(),
@@ -150,37 +362,94 @@ impl BlindTestBuilder {
             clips: HashMap::new(),
             _output_stream,
             timeline: timeline::Timeline::new(&stream_handle),
-            clip_duration: 30,
+            library: Default::default(),
+            library_open: true,
+            library_button: Default::default(),
+            presentation: None,
+            session: Default::default(),
+            teams: Default::default(),
+            remote: None,
+            remote_address: String::new(),
+            remote_button: Default::default(),
+            help: Default::default(),
+            dropped: Vec::new(),
+            drop_target: None,
+            loading: None,
+            load_generation: 0,
+            missing: Vec::new(),
+            relink: Default::default(),
+            history: Default::default(),
+            offset_drag: None,
+            undo: Default::default(),
+            redo: Default::default(),
+            clip_duration: preferences.clip_duration,
             stream_handle,
-            countdown: None,
-            image_dir: None,
-            music_dir: None,
+            countdown: preferences.countdown.clone(),
+            reveal_effect: RevealEffect::default(),
+            challenge: Challenge::default(),
+            image_dir: preferences.image_dir.clone(),
+            music_dir: preferences.music_dir.clone(),
+            preferences,
             save_as: Default::default(),
             save_path: None,
+            dirty: false,
+            exit: false,
         }
     }
 
     fn modal_update(&mut self, message: ModalMessage) -> Command<Message> {
-        let Self {
-            ref mut modal_state,
-            ref mut clip_duration,
-            ref mut countdown,
-            ..
-        } = self;
-
-        let (command, close) = match (message, &mut modal_state.inner_mut().inner) {
-            (ModalMessage::ClipBuilder(c), ModalInnerState::ClipBuilder(cb)) => {
-                cb.update(c, &mut self.clips, &mut self.music_dir, &mut self.image_dir)
-            }
-            (ModalMessage::ClipEditor(c), ModalInnerState::ClipEditor(ce)) => {
-                ce.update(c, &self.stream_handle, self.clip_duration, &mut self.clips)
-            }
+        let mut edit = None;
+        let mut defaults = None;
+        let mut import = None;
+        let mut session = None;
+
+        let (command, close) = match (message, &mut self.modal_state.inner_mut().inner) {
+            (ModalMessage::BulkImport(m), ModalInnerState::BulkImport(b)) => b.update(
+                m,
+                &self.clips,
+                &self.missing,
+                &mut self.music_dir,
+                &mut self.image_dir,
+                |clips, append| import = Some((clips, append)),
+            ),
+            (ModalMessage::ClipBuilder(c), ModalInnerState::ClipBuilder(cb)) => cb.update(
+                c,
+                &self.clips,
+                &mut self.music_dir,
+                &mut self.image_dir,
+                media_dir(self.save_path.as_deref()),
+                |clip| {
+                    edit = Some(Edit::InsertClip {
+                        clip: Box::new(clip),
+                        slots: Vec::new(),
+                    })
+                },
+            ),
+            (ModalMessage::ClipEditor(c), ModalInnerState::ClipEditor(ce)) => ce.update(
+                c,
+                &self.stream_handle,
+                self.clip_duration,
+                self.challenge,
+                &self.clips,
+                |clip| edit = Some(Edit::RemoveClip(clip)),
+            ),
+            (ModalMessage::Relink(m), ModalInnerState::Relink(r)) => r.update(m, &mut self.missing),
             (ModalMessage::GlobalSettings(m), ModalInnerState::GlobalSettings(g)) => {
                 g.update(m, |settings| {
-                    *clip_duration = settings.duration;
-                    *countdown = settings.countdown;
+                    if settings.as_default {
+                        defaults = Some((settings.duration, settings.countdown.clone()));
+                    }
+                    edit = Some(Edit::Settings(Box::new(bt_save::Settings {
+                        duration: settings.duration,
+                        countdown: settings.countdown,
+                        reveal_effect: settings.reveal_effect,
+                        challenge: settings.challenge,
+                    })))
                 })
             }
+            (ModalMessage::Teams(m), ModalInnerState::Teams(t)) => {
+                t.update(m, |s| session = Some(s))
+            }
             (m, _s) => {
                 eprintln!("Message: {:?} in invalid modal state", m);
                 return Command::none();
@@ -188,44 +457,743 @@ impl BlindTestBuilder {
         };
 
         if close {
+            self.modal_state.inner_mut().close();
             self.modal_state.show(false);
         }
+        if let Some(edit) = edit {
+            self.apply_edit(edit);
+        }
+        if let Some((clips, append)) = import {
+            let timeline = if append {
+                clips.iter().map(|clip| clip.title.clone()).collect()
+            } else {
+                Vec::new()
+            };
+            self.import_clips(clips, timeline, Vec::new());
+        }
+        if let Some(session) = session {
+            self.session = session;
+            self.store_session();
+        }
+        if let Some((clip_duration, countdown)) = defaults {
+            self.preferences.clip_duration = clip_duration;
+            self.preferences.countdown = countdown;
+            self.preferences.store();
+        }
+        self.remember_dirs();
 
         command
     }
 
+    /// Keeps the directories the files were last picked from for the next sessions
+    fn remember_dirs(&mut self) {
+        if self.preferences.music_dir != self.music_dir
+            || self.preferences.image_dir != self.image_dir
+        {
+            self.preferences.music_dir = self.music_dir.clone();
+            self.preferences.image_dir = self.image_dir.clone();
+            self.preferences.store();
+        }
+    }
+
+    fn apply_edit(&mut self, edit: Edit) {
+        let inverse = edit.apply(self);
+        self.history.record(inverse);
+        self.dirty = true;
+        self.check_selection();
+    }
+
+    fn undo(&mut self) {
+        if let Some(edit) = self.history.pop_undo() {
+            let inverse = edit.apply(self);
+            self.history.push_redo(inverse);
+            self.dirty = true;
+            self.check_selection();
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(edit) = self.history.pop_redo() {
+            let inverse = edit.apply(self);
+            self.history.push_undo(inverse);
+            self.dirty = true;
+            self.check_selection();
+        }
+    }
+
+    /// Unselects the chosen clip if it was removed
+    fn check_selection(&mut self) {
+        if let Some(selected) = &self.choosen_clip {
+            if !self.clips.contains_key(selected) {
+                self.choosen_clip = None;
+            }
+        }
+    }
+
+    /// Whether a modal is shown, edits can't be undone then as the modal could use the clips
+    fn modal_open(&self) -> bool {
+        !matches!(self.modal_state.inner().inner, ModalInnerState::None)
+    }
+
+    fn settings(&self) -> bt_save::Settings {
+        bt_save::Settings {
+            duration: self.clip_duration,
+            countdown: self.countdown.clone(),
+            reveal_effect: self.reveal_effect,
+            challenge: self.challenge,
+        }
+    }
+
+    fn set_settings(&mut self, settings: bt_save::Settings) {
+        self.clip_duration = settings.duration;
+        self.countdown = settings.countdown;
+        self.reveal_effect = settings.reveal_effect;
+        self.challenge = settings.challenge;
+    }
+
     fn save(&self) -> bt_save::SaveFile {
         bt_save::SaveFile {
-            clips: self.clips.values().map(Clip::save).collect(),
+            clips: self
+                .clips
+                .values()
+                .map(Clip::save)
+                .chain(self.missing.iter().cloned())
+                .collect(),
             timeline: self.timeline.save(),
-            settings: bt_save::Settings {
-                duration: self.clip_duration,
-                countdown: self.countdown.clone(),
-            },
+            settings: self.settings(),
         }
     }
 
+    /// Starts loading the clips of the save, the timeline is restored once they are all loaded
     fn load(&mut self, save: bt_save::SaveFile) {
         self.clips.clear();
+        self.missing.clear();
         self.choosen_clip = None;
-        for clip in save.clips {
-            self.clips.insert(
-                clip.title.clone(),
-                match Clip::load(clip) {
-                    Ok(c) => c,
-                    Err(e) => {
-                        eprintln!("Error loading clip: {:?}", e);
-                        continue;
-                    }
-                },
-            );
+        self.timeline.load(Vec::new(), &self.stream_handle);
+        self.load_clips(save.clips, Some(save.timeline));
+        self.set_settings(save.settings);
+        self.history.clear();
+        self.dirty = false;
+    }
+
+    fn store(&mut self, path: &Path) {
+        self.relocate_covers(path);
+        match bt_save::store(path, &self.save()) {
+            Ok(()) => {
+                self.dirty = false;
+                remove_recovery();
+                if let Err(e) = self.session.store(Session::path_for(path)) {
+                    eprintln!("Error saving session: {:?}", e);
+                }
+                self.preferences.add_recent(path.to_owned());
+                self.preferences.store();
+            }
+            Err(e) => eprintln!("Error saving file: {:?}", e),
+        }
+    }
+
+    /// Copies the covers extracted before the project was ever saved next to it. They are left in
+    /// the user data directory, other projects may use the same cover.
+    fn relocate_covers(&mut self, path: &Path) {
+        let (global, project) = match (media_dir(None), media_dir(Some(path))) {
+            (Some(global), Some(project)) => (global, project),
+            _ => return,
+        };
+
+        for clip in self.clips.values_mut() {
+            let cover = match &clip.image_path {
+                Some(image) if image.starts_with(&global) => image,
+                _ => continue,
+            };
+            let name = match cover.file_name() {
+                Some(name) => name,
+                None => continue,
+            };
+            let target = project.join(name);
+            if let Err(e) =
+                std::fs::create_dir_all(&project).and_then(|()| std::fs::copy(cover, &target))
+            {
+                eprintln!("Could not copy cover {:?}: {:?}", cover, e);
+                continue;
+            }
+            clip.image = Some((&target).into());
+            clip.image_path = Some(target);
+        }
+    }
+
+    /// The message of the button doing the same as the shortcut, if it is enabled
+    fn shortcut_message(&self, shortcut: Shortcut) -> Option<Message> {
+        use timeline::{TimelineAction, TimelineMessage};
+
+        let idle = self.loading.is_none();
+        let message = match shortcut {
+            Shortcut::Undo => Message::Undo,
+            Shortcut::Redo => Message::Redo,
+            Shortcut::Save if idle => Message::SaveRequest,
+            Shortcut::SaveAs if idle => Message::SaveAs,
+            Shortcut::Load if idle => Message::LoadRequest,
+            Shortcut::Save | Shortcut::SaveAs | Shortcut::Load => return None,
+            Shortcut::AddClip => Message::AddClip,
+            Shortcut::AddEntry => TimelineMessage::AddAfterSelected.into(),
+            Shortcut::EditClip => {
+                let clip = self
+                    .timeline
+                    .selected_clip()
+                    .filter(|clip| self.clips.contains_key(*clip))
+                    .map(ToOwned::to_owned)
+                    .or_else(|| self.choosen_clip.clone())?;
+                Message::EditClip(clip)
+            }
+            Shortcut::SelectPrevious => TimelineMessage::SelectPrevious.into(),
+            Shortcut::SelectNext => TimelineMessage::SelectNext.into(),
+            Shortcut::MoveUp => TimelineMessage::SelectedAction(TimelineAction::Up).into(),
+            Shortcut::MoveDown => TimelineMessage::SelectedAction(TimelineAction::Down).into(),
+            Shortcut::Delete => TimelineMessage::SelectedAction(TimelineAction::Delete).into(),
+            Shortcut::TogglePlay => TimelineMessage::ToggleSelected.into(),
+            Shortcut::Help => Message::Help,
+        };
+        Some(message)
+    }
+
+    /// What a command of the remote does, depending on whether the presentation is running
+    fn remote_message(&self, command: bt_remote::Command) -> Option<Message> {
+        use bt_remote::Command;
+        use timeline::{timeline_clip_msg, TimelineClipMessage, TimelineMessage};
+
+        let presenting = self.presentation.is_some();
+        let message = match command {
+            Command::Play if presenting => PresentationMessage::Replay.into(),
+            Command::Stop if presenting => PresentationMessage::Quit.into(),
+            Command::Next if presenting => PresentationMessage::Next.into(),
+            Command::Skip if presenting => PresentationMessage::Skip.into(),
+            Command::Back if presenting => PresentationMessage::Back.into(),
+            Command::Pause if presenting => PresentationMessage::Pause.into(),
+            Command::Present if !presenting && !self.modal_open() => Message::Present,
+            Command::Play if !presenting && !self.timeline.is_playing() => {
+                TimelineMessage::Play.into()
+            }
+            Command::Stop if !presenting => TimelineMessage::Stop.into(),
+            Command::PlayClip(index)
+                if !presenting
+                    && self.timeline.playable(index, &self.clips)
+                    && !self.timeline.playing_clips().contains(&index) =>
+            {
+                timeline_clip_msg(index, TimelineClipMessage::Play)
+            }
+            Command::StopClip(index)
+                if !presenting && self.timeline.playing_clips().contains(&index) =>
+            {
+                timeline_clip_msg(index, TimelineClipMessage::Stop)
+            }
+            _ => return None,
+        };
+        Some(message)
+    }
+
+    fn update_remote(&self) {
+        if let Some(remote) = &self.remote {
+            remote.set_state(bt_remote::State {
+                timeline: self.timeline.save(),
+                playing: self.timeline.is_playing(),
+                playing_clips: self.timeline.playing_clips(),
+                presentation: self.presentation.as_ref().map(Presentation::remote_state),
+            });
+        }
+    }
+
+    /// Writes the session next to the project, it is written when the project is first saved
+    /// otherwise
+    fn store_session(&self) {
+        if let Some(path) = &self.save_path {
+            if let Err(e) = self.session.store(Session::path_for(path)) {
+                eprintln!("Error saving session: {:?}", e);
+            }
+        }
+    }
+
+    /// Snapshots the project if it has unsaved changes, so it can be restored after a crash
+    fn autosave(&self) {
+        // Saving while loading would drop the clips that are not loaded yet
+        if !self.dirty || self.loading.is_some() {
+            return;
+        }
+
+        let path = match recovery_path() {
+            Some(path) => path,
+            None => return,
+        };
+        let recovery = Recovery {
+            project: self.save_path.clone(),
+            save: self.save(),
+        };
+        let res = match path.parent() {
+            Some(dir) => std::fs::create_dir_all(dir).map_err(bt_save::Error::from),
+            None => Ok(()),
+        }
+        .and_then(|_| recovery.store(&path));
+        if let Err(e) = res {
+            eprintln!("Error writing recovery file: {:?}", e);
+        }
+    }
+
+    fn load_from(&mut self, path: PathBuf) {
+        match bt_save::load(&path) {
+            Err(e) => eprintln!("Could not load save: {:?}", e),
+            Ok(save) => {
+                self.load(save);
+                self.session = load_session(&path);
+                remove_recovery();
+                self.preferences.add_recent(path.clone());
+                self.preferences.store();
+            }
+        }
+        self.save_path = Some(path);
+    }
+
+    fn restore(&mut self, recovery: Recovery) {
+        self.load(recovery.save);
+        self.session = recovery
+            .project
+            .as_deref()
+            .map(load_session)
+            .unwrap_or_default();
+        self.save_path = recovery.project;
+        // The project differs from what is on disk
+        self.dirty = true;
+    }
+
+    /// Writes the timeline as a M3U playlist, or as a CUE sheet of the audio-only export next to
+    /// it
+    fn export_playlist(&self, mut path: PathBuf) -> Result<(), String> {
+        use bt_export::playlist::{self, PlaylistFormat};
+
+        if path.extension().is_none() {
+            path.set_extension("m3u");
+        }
+        let format = PlaylistFormat::from_path(&path)
+            .ok_or_else(|| format!("Unknown playlist format: {}", path.display()))?;
+
+        let save = self.save();
+        let clips: HashMap<_, _> = save.clips.iter().map(|clip| (&clip.title, clip)).collect();
+        let timeline: Vec<_> = save
+            .timeline
+            .iter()
+            .flatten()
+            .filter_map(|title| clips.get(title).copied())
+            .collect();
+
+        let playlist = match format {
+            PlaylistFormat::M3u => playlist::m3u(&timeline, &save.settings),
+            PlaylistFormat::Cue => {
+                // The audio export is expected to be in the format that was last exported to
+                let extension = &self.preferences.export.extension;
+                let audio = if bt_export::AudioFormat::EXTENSIONS.contains(&extension.as_str()) {
+                    path.with_extension(extension)
+                } else {
+                    path.with_extension("mp3")
+                };
+                playlist::cue(&timeline, &save.settings, &audio)
+            }
+        };
+        std::fs::write(&path, playlist)
+            .map_err(|e| format!("Could not write {}: {}", path.display(), e))
+    }
+
+    /// Shows a confirmation before discarding unsaved changes, or does the action right away.
+    ///
+    /// `force` is set once the changes were confirmed to be discarded, the project stays modified
+    /// until another one is actually loaded.
+    fn discard_changes(&mut self, action: DiscardAction, force: bool) -> Command<Message> {
+        if self.dirty && !force {
+            self.modal_state.inner_mut().inner =
+                ModalInnerState::UnsavedChanges(modals::UnsavedChangesState::new(action));
+            self.modal_state.show(true);
+            return Command::none();
+        }
+
+        match action {
+            DiscardAction::Load(None) => Command::perform(
+                select_saveload(false, self.save_path.clone()),
+                Message::LoadFrom,
+            ),
+            DiscardAction::Load(Some(path)) => {
+                self.load_from(path);
+                Command::none()
+            }
+            DiscardAction::OpenArchive(archive) => {
+                Command::perform(open_archive(archive), Message::ArchiveOpened)
+            }
+            DiscardAction::Exit => {
+                remove_recovery();
+                self.exit = true;
+                Command::none()
+            }
+        }
+    }
+
+    fn load_clips(&mut self, clips: Vec<bt_save::ClipSave>, timeline: Option<Vec<Option<String>>>) {
+        self.load_generation += 1;
+        self.loading = Some(Loading {
+            generation: self.load_generation,
+            clips,
+            timeline,
+            loaded: 0,
+            failures: Vec::new(),
+            import: None,
+        });
+    }
+
+    /// Loads the clips in the background, then appends `timeline` to the timeline.
+    ///
+    /// The `failures` are shown along the ones of the loading.
+    fn import_clips(
+        &mut self,
+        clips: Vec<bt_save::ClipSave>,
+        timeline: Vec<String>,
+        failures: Vec<(String, String)>,
+    ) {
+        self.load_clips(clips, None);
+        if let Some(loading) = &mut self.loading {
+            loading.failures = failures;
+            loading.import = Some(Import {
+                timeline,
+                titles: Vec::new(),
+            });
+        }
+    }
+
+    fn set_clip_image(&mut self, title: &str, image: PathBuf) {
+        let mut clip = match self.clips.get(title) {
+            Some(clip) => clip.clone(),
+            None => return,
+        };
+        clip.image = Some((&image).into());
+        clip.image_path = Some(image);
+        self.apply_edit(Edit::ReplaceClip(Box::new(clip)));
+    }
+
+    /// Opens the dropped project, or uses the dropped media for the clip being built or edited.
+    /// Otherwise a single music opens the clip builder with it, several become clips right away
+    /// and an image replaces the one of the clip it was dropped on, or of the selected entry.
+    fn drop_files(&mut self, clipboard: &mut Clipboard) -> Command<Message> {
+        let paths = std::mem::take(&mut self.dropped);
+        let target = self.drop_target.take();
+        if self.presentation.is_some() || self.loading.is_some() {
+            return Command::none();
+        }
+
+        let mut music = Vec::new();
+        let mut images = Vec::new();
+        let mut project = None;
+        for path in paths {
+            match DroppedFile::of(&path) {
+                Some(DroppedFile::Music) => music.push(path),
+                Some(DroppedFile::Image) => images.push(path),
+                Some(kind) => project = project.or(Some((kind, path))),
+                None => eprintln!("Unsupported file dropped: {}", path.display()),
+            }
+        }
+        music.sort();
+        images.sort();
+
+        match &self.modal_state.inner().inner {
+            ModalInnerState::None => (),
+            ModalInnerState::ClipBuilder(_) => {
+                return self.fill_clip_builder(music, images, clipboard)
+            }
+            ModalInnerState::ClipEditor(editor) => {
+                let title = editor.clip_name().to_owned();
+                if let Some(image) = images.into_iter().next() {
+                    self.set_clip_image(&title, image);
+                }
+                return Command::none();
+            }
+            _ => return Command::none(),
+        }
+
+        match project {
+            Some((DroppedFile::Archive, archive)) => {
+                return self.discard_changes(DiscardAction::OpenArchive(archive), false)
+            }
+            Some((_, project)) => {
+                return self.discard_changes(DiscardAction::Load(Some(project)), false)
+            }
+            None => (),
+        }
+
+        match music.len() {
+            0 => {
+                let title = target.or_else(|| self.timeline.selected_clip().map(Into::into));
+                if let (Some(title), Some(image)) = (title, images.into_iter().next()) {
+                    self.set_clip_image(&title, image);
+                }
+                Command::none()
+            }
+            1 => {
+                self.modal_state.inner_mut().inner =
+                    ModalInnerState::ClipBuilder(modals::ClipBuilderState::default());
+                self.modal_state.show(true);
+                self.fill_clip_builder(music, images, clipboard)
+            }
+            _ => {
+                // Each music gets the image with the same name, like in a folder import
+                let mut titles: HashSet<String> = self
+                    .clips
+                    .keys()
+                    .cloned()
+                    .chain(self.missing.iter().map(|clip| clip.title.clone()))
+                    .collect();
+                let mut failures = Vec::new();
+                let clips = bt_save::import::pair_by_stem(&music, &images)
+                    .into_iter()
+                    .filter(|clip| {
+                        let new = titles.insert(clip.title.clone());
+                        if !new {
+                            failures.push((
+                                clip.title.clone(),
+                                "A clip with this title already exists".to_string(),
+                            ));
+                        }
+                        new
+                    })
+                    .collect();
+                self.import_clips(clips, Vec::new(), failures);
+                Command::none()
+            }
+        }
+    }
+
+    /// Gives the first music and image to the clip builder, as if they were picked
+    fn fill_clip_builder(
+        &mut self,
+        music: Vec<PathBuf>,
+        images: Vec<PathBuf>,
+        clipboard: &mut Clipboard,
+    ) -> Command<Message> {
+        let mut commands = Vec::new();
+        if let Some(music) = music.into_iter().next() {
+            commands.push(self.update(
+                ClipBuilderMessage::PickedMusic(Some(music)).into(),
+                clipboard,
+            ));
+        }
+        if let Some(image) = images.into_iter().next() {
+            commands.push(self.update(
+                ClipBuilderMessage::PickedImage(Some(image)).into(),
+                clipboard,
+            ));
+        }
+        Command::batch(commands)
+    }
+
+    fn finish_import(&mut self, import: Import) {
+        // The clips deleted or renamed while the others were loading are not imported anymore,
+        // undoing the import must not remove them again
+        let titles: Vec<_> = import
+            .titles
+            .into_iter()
+            .filter(|title| self.clips.contains_key(title))
+            .collect();
+        if titles.is_empty() {
+            return;
+        }
+
+        let mut inverse: Vec<_> = titles.iter().cloned().map(Edit::RemoveClip).collect();
+        for title in import.timeline {
+            if titles.contains(&title) {
+                let insert = Edit::TimelineInsert {
+                    index: self.timeline.len(),
+                    clip: Some(title),
+                };
+                inverse.push(insert.apply(self));
+            }
+        }
+        inverse.reverse();
+        self.history.record(Edit::Batch(inverse));
+        self.dirty = true;
+    }
+
+    fn load_progress(&mut self, progress: LoadProgress) {
+        let loading = match &mut self.loading {
+            Some(loading) => loading,
+            None => return,
+        };
+
+        match progress {
+            LoadProgress::Loaded(clip) => {
+                loading.loaded += 1;
+                if let Some(import) = &mut loading.import {
+                    import.titles.push(clip.title.clone());
+                }
+                self.clips.insert(clip.title.clone(), *clip);
+            }
+            LoadProgress::Missing(clip) => {
+                loading.loaded += 1;
+                self.missing.push(clip);
+            }
+            LoadProgress::Failed { title, error } => {
+                loading.loaded += 1;
+                loading.failures.push((title, error));
+            }
+            LoadProgress::Done => {
+                let timeline = loading.timeline.take();
+                let failures = std::mem::take(&mut loading.failures);
+                let import = loading.import.take();
+                self.loading = None;
+
+                if let Some(import) = import {
+                    self.finish_import(import);
+                }
+
+                // The edits made while the project was loading refer to the replaced timeline
+                if let Some(timeline) = timeline {
+                    self.timeline.load(timeline, &self.stream_handle);
+                    self.history.clear();
+                }
+                if !failures.is_empty() {
+                    self.modal_state.inner_mut().inner =
+                        ModalInnerState::LoadReport(modals::LoadReportState::new(failures));
+                    self.modal_state.show(true);
+                } else if !self.missing.is_empty() {
+                    self.modal_state.inner_mut().inner =
+                        ModalInnerState::Relink(modals::RelinkState::new(&self.missing));
+                    self.modal_state.show(true);
+                }
+            }
+        }
+    }
+}
+
+/// Where the media extracted from the files of the clips (like cover art) are stored, next to
+/// the project once it is saved
+fn media_dir(project: Option<&Path>) -> Option<PathBuf> {
+    match project {
+        Some(project) => Some(project.parent()?.join("media")),
+        None => Some(dirs::data_dir()?.join("blindtest-builder").join("media")),
+    }
+}
+
+/// Interval between two snapshots of a project with unsaved changes
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Interval between two checks for the commands of the remote
+const REMOTE_INTERVAL: Duration = Duration::from_millis(200);
+
+/// The files dropped at once are reported one by one, this gives them time to arrive
+const DROP_DELAY: Duration = Duration::from_millis(100);
+
+/// What a file dropped on the window is used for, judging from its extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DroppedFile {
+    Project,
+    Archive,
+    Music,
+    Image,
+}
+
+impl DroppedFile {
+    fn of(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        let is = |extensions: &[&str]| extensions.contains(&extension.as_str());
+
+        if extension == "bt" {
+            Some(Self::Project)
+        } else if extension == "bta" {
+            Some(Self::Archive)
+        } else if is(modals::MUSIC_EXTENSIONS) || is(bt_export::VIDEO_EXTENSIONS) {
+            Some(Self::Music)
+        } else if is(modals::IMAGE_EXTENSIONS) {
+            Some(Self::Image)
+        } else {
+            None
         }
-        self.timeline.load(save.timeline, &self.stream_handle);
-        self.clip_duration = save.settings.duration;
-        self.countdown = save.settings.countdown;
     }
 }
 
+/// The session of a project, projects without one start with no teams
+fn load_session(project: &Path) -> Session {
+    let path = Session::path_for(project);
+    if !path.exists() {
+        return Session::default();
+    }
+    Session::load(&path).unwrap_or_else(|e| {
+        eprintln!("Could not read session: {:?}", e);
+        Session::default()
+    })
+}
+
+/// Where the unsaved changes are snapshotted, there is one per user
+fn recovery_path() -> Option<PathBuf> {
+    Some(
+        dirs::data_dir()?
+            .join("blindtest-builder")
+            .join("recovery.json"),
+    )
+}
+
+fn remove_recovery() {
+    if let Some(path) = recovery_path().filter(|path| path.exists()) {
+        if let Err(e) = std::fs::remove_file(path) {
+            eprintln!("Could not remove recovery file: {:?}", e);
+        }
+    }
+}
+
+async fn select_playlist(base_dir: Option<PathBuf>) -> Option<PathBuf> {
+    let mut dialog = native_dialog::FileDialog::new()
+        .add_filter("Playlist", bt_save::playlist::PlaylistFormat::EXTENSIONS);
+    if let Some(p) = &base_dir {
+        dialog = dialog.set_location(p);
+    }
+
+    match dialog.show_open_single_file() {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Error getting path: {:?}", e);
+            None
+        }
+    }
+}
+
+async fn select_playlist_export(base_dir: Option<PathBuf>) -> Option<PathBuf> {
+    let mut dialog = native_dialog::FileDialog::new()
+        .add_filter("M3U", &["m3u", "m3u8"])
+        .add_filter("CUE sheet", &["cue"]);
+    if let Some(p) = &base_dir {
+        dialog = dialog.set_location(p);
+    }
+
+    match dialog.show_save_single_file() {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Error getting path: {:?}", e);
+            None
+        }
+    }
+}
+
+/// Unpacks the archive next to it, in a new folder, giving the project it contains
+async fn open_archive(archive: PathBuf) -> Result<PathBuf, String> {
+    tokio::task::spawn_blocking(move || {
+        bt_archive::load_archive(&archive, bt_archive::default_folder(&archive))
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+async fn read_playlist(
+    path: PathBuf,
+    existing: HashSet<String>,
+) -> Result<bt_save::playlist::Import, String> {
+    tokio::task::spawn_blocking(move || {
+        let entries = bt_save::playlist::read(&path).map_err(|e| e.to_string())?;
+        Ok(bt_save::playlist::resolve(entries, &existing))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
 async fn select_saveload(save: bool, base_dir: Option<PathBuf>) -> Option<PathBuf> {
     let mut dialog = native_dialog::FileDialog::new().add_filter("blindtest save", &["bt"]);
     if let Some(p) = &base_dir {
@@ -316,12 +1284,88 @@ impl Application for BlindTestBuilder {
     type Flags = ();
 
     fn new(_: Self::Flags) -> (Self, Command<Self::Message>) {
-        (Self::new(), Command::none())
+        let mut app = Self::new();
+
+        if let Some(path) = recovery_path().filter(|path| path.exists()) {
+            match Recovery::load(&path) {
+                Ok(recovery) => {
+                    app.modal_state.inner_mut().inner =
+                        ModalInnerState::Restore(modals::RestoreState::new(recovery));
+                    app.modal_state.show(true);
+                }
+                Err(e) => eprintln!("Could not read recovery file: {:?}", e),
+            }
+        }
+
+        (app, Command::none())
     }
 
     fn subscription(&self) -> Subscription<Self::Message> {
-        self.timeline
-            .subscription(&self.clips, self.countdown.clone(), self.clip_duration)
+        let loading = match &self.loading {
+            Some(loading) => {
+                Subscription::from_recipe(Loader::new(loading.generation, loading.clips.clone()))
+                    .map(Message::Loading)
+            }
+            None => Subscription::none(),
+        };
+
+        let events =
+            iced_native::subscription::events_with(|event, status| match (event, status) {
+                (iced_native::Event::Window(iced_native::window::Event::CloseRequested), _) => {
+                    Some(Message::CloseRequested)
+                }
+                (
+                    iced_native::Event::Window(iced_native::window::Event::FileDropped(path)),
+                    iced_native::event::Status::Ignored,
+                ) => Some(Message::FileDropped { path, clip: None }),
+                (
+                    iced_native::Event::Keyboard(keyboard::Event::KeyPressed {
+                        key_code,
+                        modifiers,
+                    }),
+                    iced_native::event::Status::Ignored,
+                ) => shortcuts::shortcut(key_code, modifiers).map(Message::Shortcut),
+                _ => None,
+            });
+
+        let autosave = if self.dirty {
+            iced::time::every(AUTOSAVE_INTERVAL).map(|_| Message::Autosave)
+        } else {
+            Subscription::none()
+        };
+
+        let presentation = match &self.presentation {
+            Some(presentation) => presentation.subscription(),
+            None => Subscription::none(),
+        };
+
+        let remote = if self.remote.is_some() {
+            iced::time::every(REMOTE_INTERVAL).map(|_| Message::RemotePoll)
+        } else {
+            Subscription::none()
+        };
+
+        let editor = match &self.modal_state.inner().inner {
+            ModalInnerState::ClipEditor(editor) => editor.subscription(),
+            _ => Subscription::none(),
+        };
+
+        let drop = if self.dropped.is_empty() {
+            Subscription::none()
+        } else {
+            iced::time::every(DROP_DELAY).map(|_| Message::DropFinished)
+        };
+
+        Subscription::batch(vec![
+            loading,
+            events,
+            autosave,
+            presentation,
+            remote,
+            drop,
+            editor,
+            self.timeline.subscription(&self.clips, self.settings()),
+        ])
     }
 
     fn run(settings: Settings<Self::Flags>) -> iced::Result {
@@ -357,36 +1401,206 @@ impl Application for BlindTestBuilder {
     }
 
     fn title(&self) -> String {
-        "Blind test builder".into()
+        if self.dirty {
+            "Blind test builder*".into()
+        } else {
+            "Blind test builder".into()
+        }
+    }
+
+    fn should_exit(&self) -> bool {
+        self.exit
+    }
+
+    fn mode(&self) -> window::Mode {
+        if self.presentation.is_some() {
+            window::Mode::Fullscreen
+        } else {
+            window::Mode::Windowed
+        }
     }
 
-    fn update(&mut self, message: Self::Message, _: &mut Clipboard) -> Command<Self::Message> {
+    fn update(
+        &mut self,
+        message: Self::Message,
+        clipboard: &mut Clipboard,
+    ) -> Command<Self::Message> {
         match message {
             Message::AddClip => {
                 self.modal_state.inner_mut().inner =
                     ModalInnerState::ClipBuilder(modals::ClipBuilderState::default());
                 self.modal_state.show(true)
             }
-            Message::ModalCancel | Message::ModalClosed => {
-                self.modal_state.inner_mut().close();
-                self.modal_state.show(false);
+            Message::BulkImport => {
+                self.modal_state.inner_mut().inner =
+                    ModalInnerState::BulkImport(modals::BulkImportState::default());
+                self.modal_state.show(true)
+            }
+            Message::ImportPlaylist => {
+                return Command::perform(
+                    select_playlist(self.music_dir.clone()),
+                    Message::PlaylistPicked,
+                )
+            }
+            Message::PlaylistPicked(None) => {}
+            Message::PlaylistPicked(Some(path)) => {
+                let existing = self
+                    .clips
+                    .keys()
+                    .cloned()
+                    .chain(self.missing.iter().map(|clip| clip.title.clone()))
+                    .collect();
+                return Command::perform(read_playlist(path, existing), Message::PlaylistRead);
+            }
+            Message::PlaylistRead(Err(e)) => {
+                self.modal_state.inner_mut().inner =
+                    ModalInnerState::LoadReport(modals::LoadReportState::error(
+                        "Playlist import",
+                        format!("Could not import playlist: {}", e),
+                    ));
+                self.modal_state.show(true);
+            }
+            // The clips are added in the order of the playlist, after the current timeline
+            Message::PlaylistRead(Ok(import)) => {
+                let failures = import
+                    .unresolved
+                    .iter()
+                    .map(|path| (path.display().to_string(), "File not found".to_string()))
+                    .collect();
+                self.import_clips(import.clips, import.timeline, failures);
+            }
+            Message::ExportPlaylist => {
+                return Command::perform(
+                    select_playlist_export(self.preferences.export.dir.clone()),
+                    Message::ExportPlaylistTo,
+                )
+            }
+            Message::ExportPlaylistTo(None) => {}
+            Message::ExportPlaylistTo(Some(path)) => {
+                if let Err(e) = self.export_playlist(path) {
+                    self.modal_state.inner_mut().inner = ModalInnerState::LoadReport(
+                        modals::LoadReportState::error("Playlist export", e),
+                    );
+                    self.modal_state.show(true);
+                }
             }
-            Message::Modal(m) => {
-                let cmd = self.modal_update(m);
-                if let Some(selected) = &self.choosen_clip {
-                    if !self.clips.contains_key(selected) {
-                        self.choosen_clip = None;
+            Message::Present => {
+                let clips: Vec<_> = self
+                    .timeline
+                    .save()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|title| self.clips.get(&title).cloned())
+                    .collect();
+                if !clips.is_empty() {
+                    // The presentation plays its own audio
+                    self.timeline.stop(&self.stream_handle);
+                    self.presentation = Some(Presentation::new(
+                        clips,
+                        self.clip_duration,
+                        self.challenge,
+                        &self.stream_handle,
+                    ));
+                }
+            }
+            Message::Presentation(PresentationMessage::Quit) => self.presentation = None,
+            Message::Presentation(m) => {
+                if let Some(presentation) = &mut self.presentation {
+                    let found = matches!(m, PresentationMessage::Found { .. });
+                    let command = presentation.update(m, &mut self.session);
+                    if found {
+                        self.store_session();
+                    }
+                    return command;
+                }
+            }
+            // Dropping the remote stops its server
+            Message::ToggleRemote => match self.remote.take() {
+                Some(_) => {}
+                None => match bt_remote::Remote::start(("0.0.0.0", self.preferences.remote_port)) {
+                    Ok(remote) => {
+                        // The server listens on every interface, the phone needs the address of
+                        // this computer on the network
+                        let port = remote
+                            .local_addr()
+                            .map_or(self.preferences.remote_port, |addr| addr.port());
+                        self.remote_address = match bt_remote::lan_ip() {
+                            Some(ip) => remote.url(std::net::SocketAddr::new(ip, port)),
+                            None => format!("port {}, token {}", port, remote.token()),
+                        };
+                        self.remote = Some(remote);
+                        self.update_remote();
+                    }
+                    Err(e) => eprintln!("Could not start the remote: {:?}", e),
+                },
+            },
+            Message::RemotePoll => {
+                let commands: Vec<_> = match &self.remote {
+                    Some(remote) => remote.commands().collect(),
+                    None => return Command::none(),
+                };
+                let messages: Vec<_> = commands
+                    .into_iter()
+                    .filter_map(|command| self.remote_message(command))
+                    .collect();
+                let commands: Vec<_> = messages
+                    .into_iter()
+                    .map(|message| self.update(message, clipboard))
+                    .collect();
+                self.update_remote();
+                return Command::batch(commands);
+            }
+            // The keys are used by the presentation and by the widgets of the modals
+            Message::Shortcut(shortcut) => {
+                if self.presentation.is_none() && !self.modal_open() {
+                    if let Some(message) = self.shortcut_message(shortcut) {
+                        return self.update(message, clipboard);
                     }
                 }
-                return cmd;
             }
+            Message::FileDropped { path, clip } => {
+                self.dropped.push(path);
+                if clip.is_some() {
+                    self.drop_target = clip;
+                }
+            }
+            Message::DropFinished => return self.drop_files(clipboard),
+            Message::ArchiveOpened(Err(e)) => {
+                self.modal_state.inner_mut().inner =
+                    ModalInnerState::LoadReport(modals::LoadReportState::error(
+                        "Archive",
+                        format!("Could not open archive: {}", e),
+                    ));
+                self.modal_state.show(true);
+            }
+            // The unsaved changes were discarded before unpacking it
+            Message::ArchiveOpened(Ok(project)) => self.load_from(project),
+            Message::Help => {
+                self.modal_state.inner_mut().inner = ModalInnerState::Help(Default::default());
+                self.modal_state.show(true)
+            }
+            Message::Teams => {
+                self.modal_state.inner_mut().inner =
+                    ModalInnerState::Teams(modals::TeamsState::new(self.session.clone()));
+                self.modal_state.show(true)
+            }
+            Message::ModalCancel | Message::ModalClosed => {
+                self.modal_state.inner_mut().close();
+                self.modal_state.show(false);
+            }
+            Message::Modal(m) => return self.modal_update(m),
             Message::PickedClip(clip) => self.choosen_clip = Some(clip),
             Message::EditClip(c) => {
+                let waveform = match self.clips.get(&c) {
+                    Some(clip) => modals::ClipEditorState::read_waveform(clip),
+                    None => Command::none(),
+                };
                 self.modal_state.inner_mut().inner = ModalInnerState::ClipEditor(
                     modals::ClipEditorState::new(c, &self.stream_handle)
                         .expect("Could not create stream"),
                 );
                 self.modal_state.show(true);
+                return waveform;
             }
             Message::SaveRequest => match &self.save_path {
                 None => {
@@ -396,9 +1610,8 @@ impl Application for BlindTestBuilder {
                     )
                 }
                 Some(path) => {
-                    if let Err(e) = bt_save::store(path, &self.save()) {
-                        eprintln!("Error saving file: {:?}", e);
-                    }
+                    let path = path.clone();
+                    self.store(&path);
                 }
             },
             Message::SaveAs => {
@@ -408,44 +1621,124 @@ impl Application for BlindTestBuilder {
                 )
             }
             Message::SaveTo(Some(path)) => {
-                if let Err(e) = bt_save::store(&path, &self.save()) {
-                    eprintln!("Error saving file: {:?}", e);
-                }
+                self.store(&path);
                 self.save_path = Some(path);
             }
-            Message::LoadRequest => {
-                return Command::perform(
-                    select_saveload(false, self.save_path.clone()),
-                    Message::LoadFrom,
-                )
+            Message::LoadRequest => return self.discard_changes(DiscardAction::Load(None), false),
+            Message::LoadRecent(RecentProject(path)) => {
+                return self.discard_changes(DiscardAction::Load(Some(path)), false)
             }
-            Message::LoadFrom(Some(path)) => {
-                match bt_save::load(&path) {
-                    Err(e) => eprintln!("Could not load save: {:?}", e),
-                    Ok(save) => self.load(save),
+            Message::Autosave => self.autosave(),
+            Message::Restore => {
+                let recovery = match &mut self.modal_state.inner_mut().inner {
+                    ModalInnerState::Restore(r) => r.take(),
+                    _ => None,
+                };
+                self.modal_state.inner_mut().close();
+                self.modal_state.show(false);
+                if let Some(recovery) = recovery {
+                    self.restore(recovery);
+                }
+            }
+            Message::CloseRequested => return self.discard_changes(DiscardAction::Exit, false),
+            Message::DiscardChanges(action) => {
+                self.modal_state.inner_mut().close();
+                self.modal_state.show(false);
+                return self.discard_changes(action, true);
+            }
+            Message::LoadFrom(Some(path)) => self.load_from(path),
+            Message::Loading(progress) => self.load_progress(progress),
+            Message::Relink => {
+                self.modal_state.inner_mut().inner =
+                    ModalInnerState::Relink(modals::RelinkState::new(&self.missing));
+                self.modal_state.show(true);
+            }
+            Message::Relinked(clips) => {
+                if !clips.is_empty() {
+                    self.dirty = true;
+                    self.load_clips(clips, None)
                 }
-                self.save_path = Some(path);
             }
             Message::SaveTo(None) | Message::LoadFrom(None) => {}
             Message::Timeline(m) => {
-                return self.timeline.update(
+                let export = self.preferences.export.clone();
+                let (command, edit) = self.timeline.update(
                     m,
                     &self.clips,
                     &self.stream_handle,
                     self.clip_duration,
+                    self.challenge,
+                    &mut self.preferences.export,
                 );
+                if let Some(edit) = edit {
+                    self.apply_edit(edit);
+                }
+                if self.preferences.export != export {
+                    self.preferences.store();
+                }
+                return command;
+            }
+            Message::Library(m) => {
+                if let Some(edit) = self.library.update(m, self.timeline.len()) {
+                    self.apply_edit(edit);
+                }
             }
+            Message::ToggleLibrary => self.library_open = !self.library_open,
             Message::GlobalSettings => {
-                self.modal_state.inner_mut().inner = ModalInnerState::GlobalSettings(
-                    modals::GlobalSettingsState::new(self.clip_duration, self.countdown.clone()),
-                );
+                self.modal_state.inner_mut().inner =
+                    ModalInnerState::GlobalSettings(modals::GlobalSettingsState::new(
+                        self.clip_duration,
+                        self.countdown.clone(),
+                        self.reveal_effect,
+                        self.challenge,
+                    ));
                 self.modal_state.show(true)
             }
+            // The offset is changed while dragging, and recorded as one edit once released
             Message::EditClipOffset { clip, new_offset } => {
-                self.clips
-                    .get_mut(&clip)
-                    .expect("Tried to modify non existent clip")
-                    .offset = Duration::from_secs(new_offset as u64);
+                let clip = match self.clips.get_mut(&clip) {
+                    Some(clip) => clip,
+                    None => {
+                        eprintln!("Tried to modify non existent clip {}", clip);
+                        return Command::none();
+                    }
+                };
+                if self.offset_drag.is_none() {
+                    self.offset_drag = Some(Box::new(clip.clone()));
+                }
+                clip.offset = new_offset;
+            }
+            Message::ClipOffsetReleased => {
+                if let Some(clip) = self.offset_drag.take() {
+                    self.history.record(Edit::ReplaceClip(clip));
+                    self.dirty = true;
+                }
+            }
+            Message::EditClipEffect { clip, effect } => match self.clips.get(&clip) {
+                Some(clip) => {
+                    let mut clip = clip.clone();
+                    clip.reveal_effect = effect;
+                    self.apply_edit(Edit::ReplaceClip(Box::new(clip)));
+                }
+                None => eprintln!("Tried to modify non existent clip {}", clip),
+            },
+            Message::EditClipChallenge { clip, challenge } => match self.clips.get(&clip) {
+                Some(clip) => {
+                    let mut clip = clip.clone();
+                    clip.challenge = challenge;
+                    self.apply_edit(Edit::ReplaceClip(Box::new(clip)));
+                }
+                None => eprintln!("Tried to modify non existent clip {}", clip),
+            },
+            Message::Undo => {
+                if !self.modal_open() && self.presentation.is_none() {
+                    self.undo()
+                }
+            }
+            Message::Redo => {
+                if !self.modal_open() && self.presentation.is_none() {
+                    self.redo()
+                }
             }
         }
 
@@ -453,12 +1746,143 @@ impl Application for BlindTestBuilder {
     }
 
     fn view(&mut self) -> Element<Self::Message> {
+        if let Some(presentation) = &mut self.presentation {
+            return presentation.view(&self.session);
+        }
+
         let mut edit_clip_button =
             Button::new(&mut self.edit_clip, Text::new("Edit Clip")).style(style::Button::Primary);
         if let Some(clip) = self.choosen_clip.clone() {
             edit_clip_button = edit_clip_button.on_press(Message::EditClip(clip));
         }
 
+        let mut undo_button =
+            Button::new(&mut self.undo, Text::new("Undo")).style(style::Button::Primary);
+        if self.history.can_undo() {
+            undo_button = undo_button.on_press(Message::Undo);
+        }
+        let mut redo_button =
+            Button::new(&mut self.redo, Text::new("Redo")).style(style::Button::Primary);
+        if self.history.can_redo() {
+            redo_button = redo_button.on_press(Message::Redo);
+        }
+
+        // Saving while loading would drop the clips that are not loaded yet
+        let mut load_button =
+            Button::new(&mut self.load, Text::new("Load")).style(style::Button::Primary);
+        let mut save_as_button =
+            Button::new(&mut self.save_as, Text::new("Save As")).style(style::Button::Primary);
+        let mut save_button =
+            Button::new(&mut self.save, Text::new("Save")).style(style::Button::Primary);
+        if self.loading.is_none() {
+            load_button = load_button.on_press(Message::LoadRequest);
+            save_as_button = save_as_button.on_press(Message::SaveAs);
+            save_button = save_button.on_press(Message::SaveRequest);
+        }
+
+        // Picking a project of the list loads it, nothing is ever shown as selected
+        let recent: Element<_> = if self.loading.is_none() && !self.preferences.recent.is_empty() {
+            Row::new()
+                .spacing(5)
+                .align_items(iced::Align::Center)
+                .push(Text::new("Recent:"))
+                .push(PickList::new(
+                    &mut self.recent,
+                    self.preferences
+                        .recent
+                        .iter()
+                        .cloned()
+                        .map(RecentProject)
+                        .collect::<Vec<_>>(),
+                    None,
+                    Message::LoadRecent,
+                ))
+                .into()
+        } else {
+            Space::with_width(Length::Shrink).into()
+        };
+
+        // Loading the imported clips would stop the current loading
+        let mut import_button = Button::new(&mut self.bulk_import, Text::new("Import Folder"))
+            .style(style::Button::Primary);
+        let mut playlist_button =
+            Button::new(&mut self.import_playlist, Text::new("Import Playlist"))
+                .style(style::Button::Primary);
+        if self.loading.is_none() {
+            import_button = import_button.on_press(Message::BulkImport);
+            playlist_button = playlist_button.on_press(Message::ImportPlaylist);
+        }
+
+        let remote_button = Button::new(
+            &mut self.remote_button,
+            Text::new(if self.remote.is_some() {
+                "Stop Remote"
+            } else {
+                "Start Remote"
+            }),
+        )
+        .on_press(Message::ToggleRemote)
+        .style(if self.remote.is_some() {
+            style::Button::Destructive
+        } else {
+            style::Button::Primary
+        });
+
+        let mut header = Column::new().push(Rule::horizontal(20).style(style::Rule));
+        if self.remote.is_some() {
+            header = header.push(Text::new(format!(
+                "Remote control running at {}",
+                self.remote_address
+            )));
+        }
+        if let Some(loading) = &self.loading {
+            header = header.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(iced::Align::Center)
+                    .push(Text::new(format!(
+                        "Loading clips ({}/{})",
+                        loading.loaded,
+                        loading.clips.len()
+                    )))
+                    .push(ProgressBar::new(
+                        0.0..=loading.clips.len() as f32,
+                        loading.loaded as f32,
+                    )),
+            );
+        }
+        if !self.missing.is_empty() {
+            let mut relink_button = Button::new(&mut self.relink, Text::new("Relink Media"))
+                .style(style::Button::Primary);
+            if self.loading.is_none() {
+                relink_button = relink_button.on_press(Message::Relink);
+            }
+            header = header.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(iced::Align::Center)
+                    .push(Text::new(format!(
+                        "{} clip(s) have missing media",
+                        self.missing.len()
+                    )))
+                    .push(relink_button),
+            );
+        }
+
+        let usage = self.timeline.save().into_iter().flatten().counts();
+        let timeline = self
+            .timeline
+            .view(&self.clips, &self.missing, self.clip_duration);
+        let body: Element<_> = if self.library_open {
+            Row::new()
+                .spacing(10)
+                .push(self.library.view(&self.clips, &self.missing, &usage))
+                .push(timeline)
+                .into()
+        } else {
+            timeline
+        };
+
         let content = Container::new(
             Column::new()
                 .push(
@@ -469,6 +1893,20 @@ impl Application for BlindTestBuilder {
                                 .on_press(Message::AddClip)
                                 .style(style::Button::Primary),
                         )
+                        .push(import_button)
+                        .push(playlist_button)
+                        .push(
+                            Button::new(
+                                &mut self.library_button,
+                                Text::new(if self.library_open {
+                                    "Hide Library"
+                                } else {
+                                    "Show Library"
+                                }),
+                            )
+                            .on_press(Message::ToggleLibrary)
+                            .style(style::Button::Primary),
+                        )
                         .push(
                             Container::new(
                                 Row::new()
@@ -492,26 +1930,28 @@ impl Application for BlindTestBuilder {
                                 .on_press(Message::GlobalSettings)
                                 .style(style::Button::Primary),
                         )
-                        .push(Space::with_width(Length::Fill))
-                        .push(
-                            Button::new(&mut self.load, Text::new("Load"))
-                                .style(style::Button::Primary)
-                                .on_press(Message::LoadRequest),
-                        )
                         .push(
-                            Button::new(&mut self.save_as, Text::new("Save As"))
-                                .style(style::Button::Primary)
-                                .on_press(Message::SaveAs),
+                            Button::new(&mut self.teams, Text::new("Teams"))
+                                .on_press(Message::Teams)
+                                .style(style::Button::Primary),
                         )
+                        .push(remote_button)
+                        .push(undo_button)
+                        .push(redo_button)
+                        .push(Space::with_width(Length::Fill))
+                        .push(load_button)
+                        .push(recent)
+                        .push(save_as_button)
+                        .push(save_button)
                         .push(
-                            Button::new(&mut self.save, Text::new("Save"))
-                                .style(style::Button::Primary)
-                                .on_press(Message::SaveRequest),
+                            Button::new(&mut self.help, Text::new("?"))
+                                .on_press(Message::Help)
+                                .style(style::Button::Primary),
                         )
                         .align_items(iced::Align::Center),
                 )
-                .push(Rule::horizontal(20).style(style::Rule))
-                .push(self.timeline.view(&self.clips, self.clip_duration))
+                .push(header)
+                .push(body)
                 .align_items(iced::Align::Center),
         )
         .padding(5)
@@ -649,6 +2089,34 @@ mod style {
         }
     }
 
+    /// The entry of the timeline the keyboard acts on
+    pub struct SelectedContainer;
+
+    impl container::StyleSheet for SelectedContainer {
+        fn style(&self) -> container::Style {
+            container::Style {
+                border_color: ACCENT,
+                border_radius: 5.0,
+                border_width: 2.0,
+                ..container::Style::default()
+            }
+        }
+    }
+
+    /// An entry of the timeline selected to be moved or deleted with others
+    pub struct MarkedContainer;
+
+    impl container::StyleSheet for MarkedContainer {
+        fn style(&self) -> container::Style {
+            container::Style {
+                border_color: ACTIVE,
+                border_radius: 5.0,
+                border_width: 2.0,
+                ..container::Style::default()
+            }
+        }
+    }
+
     pub struct Container;
 
     impl container::StyleSheet for Container {
@@ -0,0 +1,276 @@
+use super::ModalMessage;
+use crate::{style, Clip, Message};
+use bt_save::ClipSave;
+use iced::{
+    button, scrollable, Button, Checkbox, Color, Column, Command, Container, Element, Length, Row,
+    Scrollable, Text,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+pub(crate) const MUSIC_EXTENSIONS: &[&str] = &["mp3", "ogg", "wav", "flac"];
+pub(crate) const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp", "bmp"];
+
+#[derive(Clone, Debug)]
+pub(crate) enum BulkImportMessage {
+    SelectMusic,
+    MusicDir(Option<PathBuf>),
+    SelectImages,
+    ImageDir(Option<PathBuf>),
+    Append(bool),
+    Import,
+}
+
+impl From<BulkImportMessage> for Message {
+    fn from(m: BulkImportMessage) -> Self {
+        Message::Modal(ModalMessage::BulkImport(m))
+    }
+}
+
+/// A clip that would be imported
+struct Entry {
+    clip: ClipSave,
+    /// A clip with the same title exists, this one is skipped
+    conflict: bool,
+}
+
+#[derive(Default)]
+pub(crate) struct BulkImportState {
+    music_dir: Option<PathBuf>,
+    music_button: button::State,
+    image_dir: Option<PathBuf>,
+    image_button: button::State,
+
+    entries: Vec<Entry>,
+    scroll: scrollable::State,
+    append: bool,
+
+    error: Option<String>,
+}
+
+async fn select_folder(default_dir: Option<PathBuf>) -> Option<PathBuf> {
+    let mut dialog = native_dialog::FileDialog::new();
+    if let Some(dir) = &default_dir {
+        dialog = dialog.set_location(dir);
+    }
+
+    match dialog.show_open_single_dir() {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Error getting path: {:?}", e);
+            None
+        }
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+impl BulkImportState {
+    /// Lists the clips that the folders would give
+    fn scan(&mut self, clips: &HashMap<String, Clip>, missing: &[ClipSave]) {
+        self.entries.clear();
+        self.error = None;
+
+        let music_dir = match &self.music_dir {
+            Some(dir) => dir,
+            None => return,
+        };
+        let music = match bt_save::import::list_files(music_dir, MUSIC_EXTENSIONS) {
+            Ok(music) => music,
+            Err(e) => {
+                self.error = Some(format!("Could not read the music folder: {}", e));
+                return;
+            }
+        };
+        let images = match &self.image_dir {
+            None => Vec::new(),
+            Some(dir) => match bt_save::import::list_files(dir, IMAGE_EXTENSIONS) {
+                Ok(images) => images,
+                Err(e) => {
+                    self.error = Some(format!("Could not read the image folder: {}", e));
+                    return;
+                }
+            },
+        };
+
+        let mut titles: HashSet<String> = clips
+            .keys()
+            .cloned()
+            .chain(missing.iter().map(|clip| clip.title.clone()))
+            .collect();
+        self.entries = bt_save::import::pair_by_stem(&music, &images)
+            .into_iter()
+            .map(|clip| Entry {
+                conflict: !titles.insert(clip.title.clone()),
+                clip,
+            })
+            .collect();
+
+        if self.entries.is_empty() {
+            self.error = Some("No music was found in this folder".into());
+        }
+    }
+
+    pub(crate) fn view(&mut self) -> (String, Element<Message>, Message) {
+        let mut form = Column::new().spacing(10);
+
+        if let Some(err) = &self.error {
+            form = form.push(
+                Text::new(err)
+                    .color(Color::from_rgb8(0xff, 0x00, 0x33))
+                    .size(20),
+            );
+        }
+
+        let music_label = match &self.music_dir {
+            Some(dir) => format!("Music: {}", dir.display()),
+            None => "Select Music Folder".into(),
+        };
+        let image_label = match &self.image_dir {
+            Some(dir) => format!("Images: {}", dir.display()),
+            None => "Select Image Folder (optional)".into(),
+        };
+
+        form = form
+            .push(
+                Button::new(&mut self.music_button, Text::new(music_label))
+                    .style(style::Button::Primary)
+                    .on_press(BulkImportMessage::SelectMusic.into()),
+            )
+            .push(
+                Button::new(&mut self.image_button, Text::new(image_label))
+                    .style(style::Button::Primary)
+                    .on_press(BulkImportMessage::SelectImages.into()),
+            );
+
+        if !self.entries.is_empty() {
+            let mut table = Scrollable::new(&mut self.scroll)
+                .spacing(5)
+                .max_height(300)
+                .push(
+                    Row::new()
+                        .spacing(10)
+                        .push(Text::new("Title").width(Length::Fill))
+                        .push(Text::new("Music").width(Length::Fill))
+                        .push(Text::new("Image").width(Length::Fill)),
+                );
+            for entry in &self.entries {
+                let mut title = Text::new(if entry.conflict {
+                    format!("{} (exists, skipped)", entry.clip.title)
+                } else {
+                    entry.clip.title.clone()
+                })
+                .size(16)
+                .width(Length::Fill);
+                if entry.conflict {
+                    title = title.color(Color::from_rgb8(0xff, 0x00, 0x33));
+                }
+
+                table = table.push(
+                    Row::new()
+                        .spacing(10)
+                        .push(title)
+                        .push(
+                            Text::new(file_name(&entry.clip.music_path))
+                                .size(16)
+                                .width(Length::Fill),
+                        )
+                        .push(
+                            Text::new(
+                                entry
+                                    .clip
+                                    .image_path
+                                    .as_deref()
+                                    .map(file_name)
+                                    .unwrap_or_else(|| "Waveform".into()),
+                            )
+                            .size(16)
+                            .width(Length::Fill),
+                        ),
+                );
+            }
+
+            form = form
+                .push(
+                    Container::new(table)
+                        .padding(5)
+                        .style(style::BorderContainer),
+                )
+                .push(Checkbox::new(
+                    self.append,
+                    "Append the clips to the timeline",
+                    |append| BulkImportMessage::Append(append).into(),
+                ));
+        }
+
+        (
+            "Import Folder".into(),
+            form.into(),
+            BulkImportMessage::Import.into(),
+        )
+    }
+
+    /// `import` is given the clips to add, and whether to append them to the timeline
+    pub(crate) fn update<I: FnOnce(Vec<ClipSave>, bool)>(
+        &mut self,
+        message: BulkImportMessage,
+        clips: &HashMap<String, Clip>,
+        missing: &[ClipSave],
+        music_dir: &mut Option<PathBuf>,
+        image_dir: &mut Option<PathBuf>,
+        import: I,
+    ) -> (Command<Message>, bool) {
+        match message {
+            BulkImportMessage::SelectMusic => {
+                return (
+                    Command::perform(select_folder(music_dir.clone()), |p| {
+                        BulkImportMessage::MusicDir(p).into()
+                    }),
+                    false,
+                )
+            }
+            BulkImportMessage::SelectImages => {
+                return (
+                    Command::perform(select_folder(image_dir.clone()), |p| {
+                        BulkImportMessage::ImageDir(p).into()
+                    }),
+                    false,
+                )
+            }
+            BulkImportMessage::MusicDir(Some(dir)) => {
+                *music_dir = Some(dir.clone());
+                self.music_dir = Some(dir);
+                self.scan(clips, missing);
+            }
+            BulkImportMessage::ImageDir(Some(dir)) => {
+                *image_dir = Some(dir.clone());
+                self.image_dir = Some(dir);
+                self.scan(clips, missing);
+            }
+            BulkImportMessage::MusicDir(None) | BulkImportMessage::ImageDir(None) => (),
+            BulkImportMessage::Append(append) => self.append = append,
+            BulkImportMessage::Import => {
+                if self.entries.iter().all(|entry| entry.conflict) {
+                    self.error = Some("There are no new clips to import".into());
+                } else {
+                    let new = self
+                        .entries
+                        .drain(..)
+                        .filter(|entry| !entry.conflict)
+                        .map(|entry| entry.clip)
+                        .collect();
+                    import(new, self.append);
+                    return (Command::none(), true);
+                }
+            }
+        }
+
+        (Command::none(), false)
+    }
+}
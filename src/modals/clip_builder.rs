@@ -2,36 +2,116 @@ use crate::{style, Clip, Message};
 use iced::{
     button, text_input, Button, Color, Column, Command, Container, Element, Row, Text, TextInput,
 };
-use std::{borrow::Cow, collections::HashMap, path::PathBuf, sync::Arc, time::Duration};
+use std::{borrow::Cow, collections::HashMap, path::PathBuf, time::Duration};
 
 #[derive(Clone, Debug)]
 pub enum ClipBuilderMessage {
     TitleChanged(String),
+    ArtistChanged(String),
+    AlbumChanged(String),
     PickImage,
     PickMusic,
+    PickVideo,
     PickedImage(Option<PathBuf>),
     PickedMusic(Option<PathBuf>),
+    PickedVideo(Option<PathBuf>),
+    ReadTags(MusicTags),
+    Hashed {
+        music: PathBuf,
+        hash: Option<String>,
+    },
     Add,
 }
 
+/// What the tags of a music give to prefill the clip
+#[derive(Clone, Debug)]
+pub struct MusicTags {
+    music: PathBuf,
+    title: Option<String>,
+    artist: Option<String>,
+    album: Option<String>,
+    /// The embedded cover art, extracted to the media folder of the project
+    cover: Option<PathBuf>,
+}
+
+async fn read_tags(music: PathBuf, media_dir: Option<PathBuf>) -> MusicTags {
+    let path = music.clone();
+    let read = tokio::task::spawn_blocking(move || {
+        let tags = bt_export::media_info::read_tags(&path)?;
+        let cover = tags.cover.as_ref().zip(media_dir).and_then(|(cover, dir)| {
+            bt_save::import::store_cover(&dir, &cover.data, cover.extension()?)
+                .map_err(|e| eprintln!("Could not extract cover art: {:?}", e))
+                .ok()
+        });
+        Ok::<_, bt_export::media_info::Error>((tags, cover))
+    })
+    .await;
+
+    let (tags, cover) = match read {
+        Ok(Ok(read)) => read,
+        Ok(Err(e)) => {
+            eprintln!("Could not read tags: {:?}", e);
+            Default::default()
+        }
+        Err(e) => {
+            eprintln!("Could not read tags: {:?}", e);
+            Default::default()
+        }
+    };
+    MusicTags {
+        music,
+        title: tags.title,
+        artist: tags.artist,
+        album: tags.album,
+        cover,
+    }
+}
+
+/// Hashes the music away from the UI thread, it can take a while for a long video
+async fn hash_music(music: PathBuf) -> ClipBuilderMessage {
+    let path = music.clone();
+    let hash = match tokio::task::spawn_blocking(move || bt_save::relink::content_hash(&path)).await
+    {
+        Ok(Ok(hash)) => Some(hash),
+        Ok(Err(e)) => {
+            eprintln!("Could not hash the music: {:?}", e);
+            None
+        }
+        Err(e) => {
+            eprintln!("Could not hash the music: {:?}", e);
+            None
+        }
+    };
+    ClipBuilderMessage::Hashed { music, hash }
+}
+
 impl From<ClipBuilderMessage> for Message {
     fn from(m: ClipBuilderMessage) -> Self {
         Self::Modal(crate::ModalMessage::ClipBuilder(m))
     }
 }
 
-async fn select_file(image: bool, default_dir: Option<PathBuf>) -> Option<PathBuf> {
+#[derive(Clone, Copy)]
+enum FileKind {
+    Image,
+    Music,
+    Video,
+}
+
+async fn select_file(kind: FileKind, default_dir: Option<PathBuf>) -> Option<PathBuf> {
     let mut dialog = native_dialog::FileDialog::new();
-    if image {
-        dialog = dialog
+    dialog = match kind {
+        FileKind::Image => dialog
             .add_filter(
                 "Image file",
                 &["jpg", "jpeg", "png", "PNG", "JPG", "JPEG", "webp", "bmp"],
             )
-            .add_filter("PNG", &["png"]);
-    } else {
-        dialog = dialog.add_filter("Audio File", &["mp3", "ogg", "wav", "flac"]);
-    }
+            .add_filter("PNG", &["png"]),
+        FileKind::Music => dialog
+            .add_filter("Audio File", &["mp3", "ogg", "wav", "flac"])
+            .add_filter("Video File", bt_export::VIDEO_EXTENSIONS),
+        FileKind::Video => dialog.add_filter("Video File", bt_export::VIDEO_EXTENSIONS),
+    };
 
     if let Some(path) = &default_dir {
         dialog = dialog.set_location(path);
@@ -49,52 +129,76 @@ async fn select_file(image: bool, default_dir: Option<PathBuf>) -> Option<PathBu
 #[derive(Default)]
 pub struct ClipBuilderState {
     title_state: text_input::State,
+    artist_state: text_input::State,
+    album_state: text_input::State,
     img_state: button::State,
+    clear_image: button::State,
     music_state: button::State,
+    video_state: button::State,
+    clear_video: button::State,
 
     error: Option<String>,
     title: String,
+    artist: String,
+    album: String,
     image: Option<PathBuf>,
     music: Option<PathBuf>,
+    /// Content hash of the music, a clip added before it is computed gets it on its next load
+    music_hash: Option<(PathBuf, String)>,
+    video: Option<PathBuf>,
+    prefilled: Prefilled,
+}
+
+/// The fields holding the tags of the music, replaced by the tags of the next one picked
+#[derive(Default)]
+struct Prefilled {
+    title: bool,
+    artist: bool,
+    album: bool,
+    image: bool,
 }
 
 impl ClipBuilderState {
-    fn build(&mut self) -> Result<Clip, String> {
+    fn build(&mut self, clips: &HashMap<String, Clip>) -> Result<Clip, String> {
         if self.title.is_empty() {
             return Err("Title must not be empty".into());
         }
-        let (music, music_path) = match self.music.take() {
+        if clips.contains_key(&self.title) {
+            return Err("A clip with this title already exists".into());
+        }
+        let music_path = match self.music.take() {
             None => return Err("No music was provided".into()),
-            Some(path) => {
-                let file = match std::fs::read(&path) {
-                    Ok(f) => f,
-                    Err(e) => {
-                        self.music = Some(path);
-                        return Err(format!("Could not open music: {}", e));
-                    }
-                };
-                (Arc::new(file.into_boxed_slice()), path)
-            }
+            Some(path) => path,
         };
-        let (image, image_path) = match self.image.take() {
-            None => {
+        let info = match bt_export::media_info::probe(&music_path) {
+            Ok(info) => info,
+            Err(e) => {
                 self.music = Some(music_path);
-                return Err("No image was provided".into());
+                return Err(format!("Could not read music: {}", e));
             }
-            Some(img) => ((&img).into(), img),
         };
+        let music_hash = self
+            .music_hash
+            .take()
+            .filter(|(music, _)| music == &music_path)
+            .map(|(_, hash)| hash);
+        let image_path = self.image.take();
 
         self.error = None;
         Ok(Clip {
             title: std::mem::take(&mut self.title),
-            music,
             music_path,
-            image,
+            music_hash,
+            artist: Some(std::mem::take(&mut self.artist)).filter(|a| !a.is_empty()),
+            album: Some(std::mem::take(&mut self.album)).filter(|a| !a.is_empty()),
+            image: image_path.as_ref().map(Into::into),
             image_path,
+            video_path: self.video.take(),
+            reveal_effect: None,
+            challenge: None,
             offset: Duration::from_secs(0),
-            duration: Duration::from_secs(0),
-        }
-        .fetch_duration())
+            duration: info.duration,
+        })
     }
 
     pub(crate) fn view(&mut self) -> (String, Element<Message>, Message) {
@@ -126,6 +230,25 @@ impl ClipBuilderState {
             )
             .push(
                 Row::new()
+                    .push(Container::new(Text::new("Artist:").size(24)).padding(5))
+                    .push(
+                        TextInput::new(&mut self.artist_state, "Artist", &self.artist, |a| {
+                            ClipBuilderMessage::ArtistChanged(a).into()
+                        })
+                        .padding(10),
+                    )
+                    .push(Container::new(Text::new("Album:").size(24)).padding(5))
+                    .push(
+                        TextInput::new(&mut self.album_state, "Album", &self.album, |a| {
+                            ClipBuilderMessage::AlbumChanged(a).into()
+                        })
+                        .padding(10),
+                    )
+                    .align_items(iced::Align::Center)
+                    .padding(5),
+            )
+            .push({
+                let mut row = Row::new()
                     .push(Container::new(Text::new("Image:").size(24)).padding(5))
                     .push(
                         Button::new(
@@ -145,9 +268,18 @@ impl ClipBuilderState {
                         .style(style::Button::Primary)
                         .on_press(ClipBuilderMessage::PickImage.into()),
                     )
+                    .spacing(5)
                     .align_items(iced::Align::Center)
-                    .padding(5),
-            )
+                    .padding(5);
+                if self.image.is_some() {
+                    row = row.push(
+                        Button::new(&mut self.clear_image, Text::new("Clear"))
+                            .style(style::Button::Destructive)
+                            .on_press(ClipBuilderMessage::PickedImage(None).into()),
+                    );
+                }
+                row
+            })
             .push(
                 Row::new()
                     .push(Container::new(Text::new("Music:").size(24)).padding(5))
@@ -171,7 +303,40 @@ impl ClipBuilderState {
                     )
                     .align_items(iced::Align::Center)
                     .padding(5),
-            );
+            )
+            .push({
+                let mut row = Row::new()
+                    .push(Container::new(Text::new("Reveal video:").size(24)).padding(5))
+                    .push(
+                        Button::new(
+                            &mut self.video_state,
+                            Text::new(
+                                self.video
+                                    .as_ref()
+                                    .map(|p| {
+                                        p.file_name()
+                                            .expect("native dialog selected file name")
+                                            .to_string_lossy()
+                                    })
+                                    .unwrap_or(Cow::Borrowed("No Video Selected")),
+                            ),
+                        )
+                        .padding(10)
+                        .style(style::Button::Primary)
+                        .on_press(ClipBuilderMessage::PickVideo.into()),
+                    )
+                    .spacing(5)
+                    .align_items(iced::Align::Center)
+                    .padding(5);
+                if self.video.is_some() {
+                    row = row.push(
+                        Button::new(&mut self.clear_video, Text::new("Clear"))
+                            .style(style::Button::Destructive)
+                            .on_press(ClipBuilderMessage::PickedVideo(None).into()),
+                    );
+                }
+                row
+            });
 
         (
             "Add Clip".into(),
@@ -180,20 +345,31 @@ impl ClipBuilderState {
         )
     }
 
-    pub(crate) fn update(
+    pub(crate) fn update<A: FnOnce(Clip)>(
         &mut self,
         message: ClipBuilderMessage,
-        clips: &mut HashMap<String, Clip>,
+        clips: &HashMap<String, Clip>,
         music_dir: &mut Option<PathBuf>,
         image_dir: &mut Option<PathBuf>,
+        media_dir: Option<PathBuf>,
+        add: A,
     ) -> (Command<Message>, bool) {
         match message {
             ClipBuilderMessage::TitleChanged(t) => {
                 self.title = t;
+                self.prefilled.title = false;
+            }
+            ClipBuilderMessage::ArtistChanged(a) => {
+                self.artist = a;
+                self.prefilled.artist = false;
+            }
+            ClipBuilderMessage::AlbumChanged(a) => {
+                self.album = a;
+                self.prefilled.album = false;
             }
             ClipBuilderMessage::PickImage => {
                 return (
-                    Command::perform(select_file(true, image_dir.clone()), |p| {
+                    Command::perform(select_file(FileKind::Image, image_dir.clone()), |p| {
                         ClipBuilderMessage::PickedImage(p).into()
                     }),
                     false,
@@ -201,32 +377,80 @@ impl ClipBuilderState {
             }
             ClipBuilderMessage::PickMusic => {
                 return (
-                    Command::perform(select_file(false, music_dir.clone()), |p| {
+                    Command::perform(select_file(FileKind::Music, music_dir.clone()), |p| {
                         ClipBuilderMessage::PickedMusic(p).into()
                     }),
                     false,
                 );
             }
+            ClipBuilderMessage::PickVideo => {
+                return (
+                    Command::perform(select_file(FileKind::Video, music_dir.clone()), |p| {
+                        ClipBuilderMessage::PickedVideo(p).into()
+                    }),
+                    false,
+                );
+            }
             ClipBuilderMessage::PickedImage(img) => {
-                *image_dir = img.clone().map(|mut p| {
-                    p.pop();
-                    p
-                });
-                self.image = img
+                if let Some(mut dir) = img.clone() {
+                    dir.pop();
+                    *image_dir = Some(dir);
+                }
+                self.image = img;
+                self.prefilled.image = false;
             }
             ClipBuilderMessage::PickedMusic(msc) => {
                 *music_dir = msc.clone().map(|mut p| {
                     p.pop();
                     p
                 });
-                self.music = msc;
+                self.music = msc.clone();
+                if let Some(music) = msc {
+                    if bt_export::is_video(&music) && self.video.is_none() {
+                        self.video = Some(music.clone());
+                    }
+                    return (
+                        Command::batch(vec![
+                            Command::perform(read_tags(music.clone(), media_dir), |tags| {
+                                ClipBuilderMessage::ReadTags(tags).into()
+                            }),
+                            Command::perform(hash_music(music), Into::into),
+                        ]),
+                        false,
+                    );
+                }
+            }
+            // What was typed or picked is kept, the tags fill the blanks and replace the tags of
+            // the music picked before
+            ClipBuilderMessage::ReadTags(tags) => {
+                if self.music.as_ref() == Some(&tags.music) {
+                    let fill = |field: &mut String, prefilled: &mut bool, value: Option<String>| {
+                        if field.is_empty() || *prefilled {
+                            *prefilled = value.is_some();
+                            *field = value.unwrap_or_default();
+                        }
+                    };
+                    fill(&mut self.title, &mut self.prefilled.title, tags.title);
+                    fill(&mut self.artist, &mut self.prefilled.artist, tags.artist);
+                    fill(&mut self.album, &mut self.prefilled.album, tags.album);
+                    if self.image.is_none() || self.prefilled.image {
+                        self.prefilled.image = tags.cover.is_some();
+                        self.image = tags.cover;
+                    }
+                }
+            }
+            ClipBuilderMessage::Hashed { music, hash } => {
+                if self.music.as_ref() == Some(&music) {
+                    self.music_hash = hash.map(|hash| (music, hash));
+                }
             }
-            ClipBuilderMessage::Add => match self.build() {
+            ClipBuilderMessage::PickedVideo(video) => self.video = video,
+            ClipBuilderMessage::Add => match self.build(clips) {
                 Err(err) => {
                     self.error = Some(err.into());
                 }
                 Ok(clip) => {
-                    clips.insert(clip.title.clone(), clip);
+                    add(clip);
                     return (Command::none(), true);
                 }
             },
@@ -1,16 +1,142 @@
 use super::ModalMessage;
-use crate::{style, Clip, Message};
-use iced::{button, slider, Button, Column, Command, Element, Image, Row, Slider, Text};
-use rodio::{OutputStreamHandle, Sink};
-use std::collections::HashMap;
+use crate::{
+    stream, style,
+    waveform::{self, Waveform},
+    Clip, Message,
+};
+use bt_save::{Challenge, RevealEffect};
+use iced::{
+    button, pick_list, Button, Canvas, Column, Command, Element, Length, PickList, Row,
+    Subscription, Text,
+};
+use rodio::{OutputStreamHandle, Sink, Source};
+use std::{
+    collections::HashMap,
+    fmt,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+/// The reveal effect of a clip, that can use the project default
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct EffectChoice(Option<RevealEffect>);
+
+impl EffectChoice {
+    const ALL: [EffectChoice; 5] = [
+        EffectChoice(None),
+        EffectChoice(Some(RevealEffect::Hidden)),
+        EffectChoice(Some(RevealEffect::Blur)),
+        EffectChoice(Some(RevealEffect::Pixelate)),
+        EffectChoice(Some(RevealEffect::Zoom)),
+    ];
+}
+
+/// The challenge of a clip, that can use the project default
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct ChallengeChoice(Option<Challenge>);
+
+impl ChallengeChoice {
+    fn all() -> Vec<ChallengeChoice> {
+        std::iter::once(ChallengeChoice(None))
+            .chain(Challenge::PRESETS.iter().map(|&c| ChallengeChoice(Some(c))))
+            .collect()
+    }
+}
+
+impl fmt::Display for ChallengeChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            None => write!(f, "Project default"),
+            Some(challenge) => challenge.fmt(f),
+        }
+    }
+}
+
+impl fmt::Display for EffectChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            None => write!(f, "Project default"),
+            Some(effect) => effect.fmt(f),
+        }
+    }
+}
+
+/// What the sink is playing, to move the playhead over the waveform
+struct Playback {
+    started: Instant,
+    /// Position in the music when it started, in seconds
+    from: f32,
+    /// Seconds of music played per second, negative when reversed
+    rate: f32,
+    /// Where the playhead stops, the music can go on in silence
+    until: f32,
+}
+
+impl Playback {
+    /// The clip as heard by the players
+    fn clip(clip: &Clip, duration: u32, challenge: Challenge) -> Self {
+        let offset = clip.offset.as_secs_f32();
+        let duration = duration as f32;
+        let (from, rate, until) = match clip.challenge.unwrap_or(challenge) {
+            Challenge::Normal => (offset, 1., offset + duration),
+            Challenge::Reversed => (offset + duration, -1., offset),
+            Challenge::SpedUp { percent } => {
+                let rate = percent as f32 / 100.;
+                (offset, rate, offset + duration * rate)
+            }
+            Challenge::IntroOnly { seconds } => (offset, 1., offset + duration.min(seconds as f32)),
+        };
+        Self {
+            started: Instant::now(),
+            from,
+            rate,
+            until,
+        }
+    }
+
+    fn position(&self) -> f32 {
+        let position = self.from + self.started.elapsed().as_secs_f32() * self.rate;
+        if self.rate < 0. {
+            position.max(self.until)
+        } else {
+            position.min(self.until)
+        }
+    }
+}
+
+/// Minutes, seconds and tenths of a second
+fn format_time(time: Duration) -> String {
+    let seconds = time.as_secs_f32();
+    format!("{}:{:04.1}", (seconds / 60.) as u32, seconds % 60.)
+}
+
+async fn read_waveform(
+    clip: String,
+    music: PathBuf,
+    duration: Duration,
+) -> (String, Result<Vec<f32>, String>) {
+    let peaks =
+        tokio::task::spawn_blocking(move || stream::peaks(&music, duration, waveform::BARS))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|peaks| peaks);
+    (clip, peaks)
+}
+
+const PLAYHEAD_INTERVAL: Duration = Duration::from_millis(50);
+const WAVEFORM_WIDTH: u16 = 800;
+const WAVEFORM_HEIGHT: u16 = 120;
 
 pub(crate) struct ClipEditorState {
     clip: String,
 
     playing: bool,
+    playback: Option<Playback>,
     audio_button: button::State,
     delete_button: button::State,
-    offset_slider: slider::State,
+    waveform: Waveform,
+    effect_list: pick_list::State<EffectChoice>,
+    challenge_list: pick_list::State<ChallengeChoice>,
     sink: Sink,
 }
 
@@ -21,38 +147,92 @@ impl ClipEditorState {
 
     pub(crate) fn new(clip: String, stream_handle: &OutputStreamHandle) -> anyhow::Result<Self> {
         Ok(Self {
+            waveform: Waveform::new(clip.clone()),
             clip,
             playing: false,
+            playback: None,
             audio_button: Default::default(),
             delete_button: Default::default(),
-            offset_slider: Default::default(),
+            effect_list: Default::default(),
+            challenge_list: Default::default(),
             sink: Sink::try_new(stream_handle)?,
         })
     }
 
-    pub(crate) fn update(
+    /// Decodes the music in the background to draw its waveform
+    pub(crate) fn read_waveform(clip: &Clip) -> Command<Message> {
+        Command::perform(
+            read_waveform(clip.title.clone(), clip.music_path.clone(), clip.duration),
+            |(clip, peaks)| ClipEditorMessage::Waveform { clip, peaks }.into(),
+        )
+    }
+
+    /// Moves the playhead while the clip plays
+    pub(crate) fn subscription(&self) -> Subscription<Message> {
+        if self.playing {
+            iced::time::every(PLAYHEAD_INTERVAL).map(|_| ClipEditorMessage::Tick.into())
+        } else {
+            Subscription::none()
+        }
+    }
+
+    pub(crate) fn update<D: FnOnce(String)>(
         &mut self,
         message: ClipEditorMessage,
         stream_handle: &OutputStreamHandle,
         duration: u32,
-        clips: &mut HashMap<String, crate::Clip>,
+        challenge: Challenge,
+        clips: &HashMap<String, crate::Clip>,
+        delete: D,
     ) -> (Command<Message>, bool) {
         let clip = clips.get(&self.clip).expect("clip was deleted somehow");
 
         match message {
             ClipEditorMessage::PlayClip => {
-                match clip.audio(duration) {
+                match clip.audio(duration, challenge) {
                     Ok(a) => self.sink.append(a),
                     Err(e) => eprintln!("Could not decode audio: {:?}", e),
                 };
-                self.playing = true
+                self.playing = true;
+                self.playback = Some(Playback::clip(clip, duration, challenge));
+            }
+            // The music is previewed as is, without the challenge
+            ClipEditorMessage::Seek(position) => {
+                self.sink = Sink::try_new(stream_handle).expect("could not create new sink");
+                let length = Duration::from_secs(duration as u64);
+                match stream::open(&clip.music_path, position) {
+                    Ok(music) => self.sink.append(music.take_duration(length)),
+                    Err(e) => eprintln!("Could not decode audio: {:?}", e),
+                }
+                self.playing = true;
+                self.playback = Some(Playback {
+                    started: Instant::now(),
+                    from: position.as_secs_f32(),
+                    rate: 1.,
+                    until: (position + length).as_secs_f32(),
+                });
             }
             ClipEditorMessage::StopClip => {
                 self.sink = Sink::try_new(stream_handle).expect("could not create new sink");
-                self.playing = false
+                self.playing = false;
+                self.playback = None;
+            }
+            ClipEditorMessage::Tick => {
+                if self.sink.empty() {
+                    self.playing = false;
+                    self.playback = None;
+                }
+            }
+            ClipEditorMessage::Waveform { clip, peaks } => {
+                if clip == self.clip {
+                    self.waveform.set_peaks(peaks.unwrap_or_else(|e| {
+                        eprintln!("Could not read waveform: {}", e);
+                        Vec::new()
+                    }));
+                }
             }
             ClipEditorMessage::Delete => {
-                clips.remove(&self.clip);
+                delete(self.clip.clone());
                 return (Command::none(), true);
             }
         }
@@ -84,25 +264,56 @@ impl ClipEditorState {
             ClipEditorMessage::PlayClip.into()
         });
 
-        let title = clip.title.clone();
+        self.waveform.set_window(
+            clip.duration,
+            clip.offset,
+            Duration::from_secs(clip_duration as u64),
+        );
+        self.waveform.playhead = self.playback.as_ref().map(Playback::position);
+
+        let effect_title = clip.title.clone();
+        let challenge_title = clip.title.clone();
 
         let content = Column::new()
             .spacing(5)
-            .push(Image::new(clip.image.clone()))
+            .push(clip.image_view())
+            .push(Text::new(format!(
+                "Played from {} to {} (drag the highlighted part, click to listen from there)",
+                format_time(clip.offset),
+                format_time(clip.offset + Duration::from_secs(clip_duration as u64)),
+            )))
+            .push(
+                Canvas::new(&mut self.waveform)
+                    .width(Length::Units(WAVEFORM_WIDTH))
+                    .height(Length::Units(WAVEFORM_HEIGHT)),
+            )
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(iced::Align::Center)
+                    .push(Text::new("Image during countdown:"))
+                    .push(PickList::new(
+                        &mut self.effect_list,
+                        EffectChoice::ALL.to_vec(),
+                        Some(EffectChoice(clip.reveal_effect)),
+                        move |choice| Message::EditClipEffect {
+                            clip: effect_title.clone(),
+                            effect: choice.0,
+                        },
+                    )),
+            )
             .push(
                 Row::new()
+                    .spacing(10)
                     .align_items(iced::Align::Center)
-                    .push(Text::new(&format!(
-                        "Start offset ({}):",
-                        clip.offset.as_secs()
-                    )))
-                    .push(Slider::new(
-                        &mut self.offset_slider,
-                        0.0..=(clip.duration.as_secs_f64() - clip_duration as f64),
-                        clip.offset.as_secs_f64(),
-                        move |new_offset| Message::EditClipOffset {
-                            clip: title.clone(),
-                            new_offset: new_offset as u32,
+                    .push(Text::new("Challenge:"))
+                    .push(PickList::new(
+                        &mut self.challenge_list,
+                        ChallengeChoice::all(),
+                        Some(ChallengeChoice(clip.challenge)),
+                        move |choice| Message::EditClipChallenge {
+                            clip: challenge_title.clone(),
+                            challenge: choice.0,
                         },
                     )),
             )
@@ -126,6 +337,14 @@ impl ClipEditorState {
 pub(crate) enum ClipEditorMessage {
     PlayClip,
     StopClip,
+    /// Plays the music from this position
+    Seek(Duration),
+    /// Moves the playhead, and notices the end of the playback
+    Tick,
+    Waveform {
+        clip: String,
+        peaks: Result<Vec<f32>, String>,
+    },
     Delete,
 }
 
@@ -1,7 +1,9 @@
 use super::ModalMessage;
 use crate::{style, Message};
+use bt_save::{Challenge, RevealEffect};
 use iced::{
-    button, text_input, Button, Color, Column, Command, Container, Element, Row, Text, TextInput,
+    button, pick_list, text_input, Button, Checkbox, Color, Column, Command, Container, Element,
+    PickList, Row, Text, TextInput,
 };
 use std::{borrow::Cow, path::PathBuf};
 
@@ -12,12 +14,24 @@ pub(crate) struct GlobalSettingsState {
     countdown: Option<PathBuf>,
     countdown_button: button::State,
 
+    reveal_effect: RevealEffect,
+    reveal_effect_list: pick_list::State<RevealEffect>,
+
+    challenge: Challenge,
+    challenge_list: pick_list::State<Challenge>,
+
+    as_default: bool,
+
     error: Option<String>,
 }
 
 pub(crate) struct Settings {
     pub duration: u32,
     pub countdown: Option<PathBuf>,
+    pub reveal_effect: RevealEffect,
+    pub challenge: Challenge,
+    /// The duration and countdown should be used for new projects
+    pub as_default: bool,
 }
 
 async fn select_file() -> Option<PathBuf> {
@@ -41,15 +55,28 @@ impl GlobalSettingsState {
         Ok(Settings {
             duration,
             countdown: self.countdown.take(),
+            reveal_effect: self.reveal_effect,
+            challenge: self.challenge,
+            as_default: self.as_default,
         })
     }
 
-    pub(crate) fn new(current_duration: u32, countdown: Option<PathBuf>) -> Self {
+    pub(crate) fn new(
+        current_duration: u32,
+        countdown: Option<PathBuf>,
+        reveal_effect: RevealEffect,
+        challenge: Challenge,
+    ) -> Self {
         GlobalSettingsState {
             duration_input: Default::default(),
             current_duration: current_duration.to_string(),
             countdown_button: Default::default(),
             countdown,
+            reveal_effect,
+            reveal_effect_list: Default::default(),
+            challenge,
+            challenge_list: Default::default(),
+            as_default: false,
             error: None,
         }
     }
@@ -107,7 +134,36 @@ impl GlobalSettingsState {
                     )
                     .align_items(iced::Align::Center)
                     .padding(5),
-            );
+            )
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(iced::Align::Center)
+                    .push(Text::new("Image during countdown:").size(24))
+                    .push(PickList::new(
+                        &mut self.reveal_effect_list,
+                        RevealEffect::ALL.to_vec(),
+                        Some(self.reveal_effect),
+                        wrap_gs(GlobalSettingsMessage::RevealEffect),
+                    )),
+            )
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(iced::Align::Center)
+                    .push(Text::new("Challenge:").size(24))
+                    .push(PickList::new(
+                        &mut self.challenge_list,
+                        Challenge::PRESETS.to_vec(),
+                        Some(self.challenge),
+                        wrap_gs(GlobalSettingsMessage::Challenge),
+                    )),
+            )
+            .push(Checkbox::new(
+                self.as_default,
+                "Use the duration and countdown for new projects",
+                wrap_gs(GlobalSettingsMessage::AsDefault),
+            ));
 
         (
             "Global Settings".into(),
@@ -141,6 +197,9 @@ impl GlobalSettingsState {
                 )
             }
             GlobalSettingsMessage::CountDownPath(p) => self.countdown = p,
+            GlobalSettingsMessage::RevealEffect(e) => self.reveal_effect = e,
+            GlobalSettingsMessage::Challenge(c) => self.challenge = c,
+            GlobalSettingsMessage::AsDefault(d) => self.as_default = d,
         }
 
         (Command::none(), false)
@@ -152,6 +211,9 @@ pub(crate) enum GlobalSettingsMessage {
     UpdateDuration(String),
     CountDownPath(Option<PathBuf>),
     SelectCountdown,
+    RevealEffect(RevealEffect),
+    Challenge(Challenge),
+    AsDefault(bool),
     UpdateSettings,
 }
 
@@ -0,0 +1,26 @@
+use crate::{shortcuts::BINDINGS, Message};
+use iced::{Column, Element, Length, Row, Text};
+
+/// Lists the keyboard shortcuts
+#[derive(Default)]
+pub(crate) struct HelpState;
+
+impl HelpState {
+    pub(crate) fn view(&mut self) -> (String, Element<Message>, Message) {
+        let mut list = Column::new().spacing(5);
+        for (keys, action) in BINDINGS {
+            list = list.push(
+                Row::new()
+                    .spacing(10)
+                    .push(Text::new(*keys).size(18).width(Length::FillPortion(2)))
+                    .push(Text::new(*action).size(18).width(Length::FillPortion(3))),
+            );
+        }
+
+        (
+            "Keyboard Shortcuts".into(),
+            list.into(),
+            Message::ModalClosed,
+        )
+    }
+}
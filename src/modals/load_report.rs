@@ -0,0 +1,50 @@
+use crate::Message;
+use iced::{scrollable, Column, Element, Scrollable, Text};
+
+/// Lists the clips of a project that could not be loaded, or the error that stopped an import or
+/// an export
+pub(crate) struct LoadReportState {
+    title: String,
+    summary: String,
+    failures: Vec<(String, String)>,
+    scroll: scrollable::State,
+}
+
+impl LoadReportState {
+    pub(crate) fn new(failures: Vec<(String, String)>) -> Self {
+        Self {
+            title: "Load report".into(),
+            summary: format!("{} clip(s) could not be loaded:", failures.len()),
+            failures,
+            scroll: Default::default(),
+        }
+    }
+
+    /// Reports an error that stopped the action named `title`
+    pub(crate) fn error(title: &str, error: String) -> Self {
+        Self {
+            title: title.into(),
+            summary: error,
+            failures: Vec::new(),
+            scroll: Default::default(),
+        }
+    }
+
+    pub(crate) fn view(&mut self) -> (String, Element<Message>, Message) {
+        let mut list = Scrollable::new(&mut self.scroll)
+            .spacing(10)
+            .max_height(300)
+            .push(Text::new(&self.summary));
+
+        for (title, error) in &self.failures {
+            list = list.push(
+                Column::new()
+                    .spacing(2)
+                    .push(Text::new(title).size(24))
+                    .push(Text::new(error).size(16)),
+            );
+        }
+
+        (self.title.clone(), list.into(), Message::ModalClosed)
+    }
+}
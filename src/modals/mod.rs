@@ -4,34 +4,67 @@ use std::collections::HashMap;
 
 use crate::{style, Clip, Message};
 
+mod bulk_import;
+pub(crate) use bulk_import::{
+    BulkImportMessage, BulkImportState, IMAGE_EXTENSIONS, MUSIC_EXTENSIONS,
+};
 mod clip_builder;
 pub use clip_builder::{ClipBuilderMessage, ClipBuilderState};
 mod clip_editor;
 pub(crate) use clip_editor::{ClipEditorMessage, ClipEditorState};
 mod global_settings;
 pub(crate) use global_settings::{GlobalSettingsMessage, GlobalSettingsState};
+mod help;
+pub(crate) use help::HelpState;
+mod load_report;
+pub(crate) use load_report::LoadReportState;
+mod relink;
+pub(crate) use relink::{RelinkMessage, RelinkState};
+mod restore;
+pub(crate) use restore::RestoreState;
+mod teams;
+pub(crate) use teams::{TeamsMessage, TeamsState};
+mod unsaved_changes;
+pub(crate) use unsaved_changes::{DiscardAction, UnsavedChangesState};
 
 #[derive(Clone, Debug)]
 pub(crate) enum ModalMessage {
+    BulkImport(BulkImportMessage),
     ClipBuilder(ClipBuilderMessage),
     ClipEditor(ClipEditorMessage),
     GlobalSettings(GlobalSettingsMessage),
+    Relink(RelinkMessage),
+    Teams(TeamsMessage),
 }
 
 pub(crate) enum ModalInnerState {
+    BulkImport(BulkImportState),
     ClipBuilder(ClipBuilderState),
     ClipEditor(ClipEditorState),
     GlobalSettings(GlobalSettingsState),
+    Help(HelpState),
+    LoadReport(LoadReportState),
+    Relink(RelinkState),
+    Restore(RestoreState),
+    Teams(TeamsState),
+    UnsavedChanges(UnsavedChangesState),
     None,
 }
 
 impl ModalInnerState {
     fn has_cancel(&self) -> bool {
         match self {
-            ModalInnerState::ClipBuilder(_)
+            ModalInnerState::BulkImport(_)
+            | ModalInnerState::ClipBuilder(_)
             | ModalInnerState::ClipEditor(_)
-            | ModalInnerState::GlobalSettings(_) => true,
-            ModalInnerState::None => false,
+            | ModalInnerState::GlobalSettings(_)
+            | ModalInnerState::Relink(_)
+            | ModalInnerState::Restore(_)
+            | ModalInnerState::Teams(_)
+            | ModalInnerState::UnsavedChanges(_) => true,
+            ModalInnerState::Help(_) | ModalInnerState::LoadReport(_) | ModalInnerState::None => {
+                false
+            }
         }
     }
 }
@@ -62,6 +95,7 @@ impl ModalState {
     ) -> Element<Message> {
         let has_cancel = self.inner.has_cancel();
         let (title, content, confirm) = match &mut self.inner {
+            ModalInnerState::BulkImport(b) => b.view(),
             ModalInnerState::ClipBuilder(c) => c.view(),
             ModalInnerState::ClipEditor(c) => c.view(
                 clips
@@ -74,6 +108,12 @@ impl ModalState {
                 return Space::new(iced::Length::Shrink, iced::Length::Shrink).into();
             }
             ModalInnerState::GlobalSettings(g) => g.view(),
+            ModalInnerState::Help(h) => h.view(),
+            ModalInnerState::LoadReport(l) => l.view(),
+            ModalInnerState::Relink(r) => r.view(),
+            ModalInnerState::Restore(r) => r.view(),
+            ModalInnerState::Teams(t) => t.view(),
+            ModalInnerState::UnsavedChanges(u) => u.view(),
         };
 
         let mut controls = Row::new().spacing(10).padding(5).width(iced::Length::Fill);
@@ -0,0 +1,275 @@
+use super::ModalMessage;
+use crate::{style, Message};
+use bt_save::relink::{MediaKind, Wanted};
+use iced::{
+    button, scrollable, Button, Color, Column, Command, Container, Element, Row, Scrollable, Text,
+};
+use std::path::PathBuf;
+
+#[derive(Clone, Debug)]
+pub(crate) enum RelinkMessage {
+    Locate(usize),
+    Located(usize, Option<PathBuf>),
+    Search,
+    SearchIn(Option<PathBuf>),
+    Found(Vec<(usize, PathBuf)>),
+    Apply,
+}
+
+impl From<RelinkMessage> for Message {
+    fn from(m: RelinkMessage) -> Self {
+        Message::Modal(ModalMessage::Relink(m))
+    }
+}
+
+/// A missing file of a clip
+struct Entry {
+    title: String,
+    kind: MediaKind,
+    path: PathBuf,
+    hash: Option<String>,
+    new_path: Option<PathBuf>,
+    locate: button::State,
+}
+
+pub(crate) struct RelinkState {
+    entries: Vec<Entry>,
+    search: button::State,
+    scroll: scrollable::State,
+    searching: bool,
+    error: Option<String>,
+}
+
+async fn select_file(path: PathBuf) -> Option<PathBuf> {
+    let mut dialog = native_dialog::FileDialog::new();
+    if let Some(dir) = path.parent().filter(|dir| dir.exists()) {
+        dialog = dialog.set_location(dir);
+    }
+
+    match dialog.show_open_single_file() {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Error getting path: {:?}", e);
+            None
+        }
+    }
+}
+
+async fn select_folder() -> Option<PathBuf> {
+    match native_dialog::FileDialog::new().show_open_single_dir() {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Error getting path: {:?}", e);
+            None
+        }
+    }
+}
+
+async fn search(folder: PathBuf, wanted: Vec<(usize, Wanted)>) -> Vec<(usize, PathBuf)> {
+    let found = tokio::task::spawn_blocking(move || {
+        let (indices, wanted): (Vec<_>, Vec<_>) = wanted.into_iter().unzip();
+        let found = bt_save::relink::search(&folder, &wanted);
+        indices
+            .into_iter()
+            .zip(found)
+            .filter_map(|(index, path)| Some((index, path?)))
+            .collect()
+    })
+    .await;
+
+    match found {
+        Ok(found) => found,
+        Err(e) => {
+            eprintln!("Error searching for media: {:?}", e);
+            Vec::new()
+        }
+    }
+}
+
+impl RelinkState {
+    pub(crate) fn new(missing: &[bt_save::ClipSave]) -> Self {
+        let entries = missing
+            .iter()
+            .flat_map(|clip| {
+                clip.missing_media().into_iter().map(move |kind| Entry {
+                    title: clip.title.clone(),
+                    kind,
+                    path: clip
+                        .media_path(kind)
+                        .expect("missing media has a path")
+                        .to_owned(),
+                    hash: match kind {
+                        MediaKind::Music => clip.music_hash.clone(),
+                        MediaKind::Image | MediaKind::Video => None,
+                    },
+                    new_path: None,
+                    locate: Default::default(),
+                })
+            })
+            .collect();
+
+        Self {
+            entries,
+            search: Default::default(),
+            scroll: Default::default(),
+            searching: false,
+            error: None,
+        }
+    }
+
+    pub(crate) fn view(&mut self) -> (String, Element<Message>, Message) {
+        let mut form = Column::new().spacing(10);
+
+        if let Some(err) = &self.error {
+            form = form.push(
+                Text::new(err)
+                    .color(Color::from_rgb8(0xff, 0x00, 0x33))
+                    .size(20),
+            );
+        }
+
+        let mut search = Button::new(
+            &mut self.search,
+            Text::new(if self.searching {
+                "Searching..."
+            } else {
+                "Search in Folder"
+            }),
+        )
+        .style(style::Button::Primary);
+        if !self.searching {
+            search = search.on_press(RelinkMessage::Search.into());
+        }
+        form = form
+            .push(search)
+            .push(Text::new("Save the project to keep the new paths").size(16));
+
+        let mut list = Scrollable::new(&mut self.scroll)
+            .spacing(10)
+            .max_height(300);
+        for (index, entry) in self.entries.iter_mut().enumerate() {
+            let location = match &entry.new_path {
+                Some(path) => format!("Found: {}", path.display()),
+                None => format!("Missing: {}", entry.path.display()),
+            };
+
+            list = list.push(
+                Container::new(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(iced::Align::Center)
+                        .push(
+                            Column::new()
+                                .push(Text::new(format!("{} ({})", entry.title, entry.kind)))
+                                .push(Text::new(location).size(16))
+                                .width(iced::Length::Fill),
+                        )
+                        .push(
+                            Button::new(&mut entry.locate, Text::new("Locate"))
+                                .style(style::Button::Primary)
+                                .on_press(RelinkMessage::Locate(index).into()),
+                        ),
+                )
+                .padding(5)
+                .style(style::BorderContainer),
+            );
+        }
+        form = form.push(list);
+
+        (
+            "Relink Missing Media".into(),
+            form.into(),
+            RelinkMessage::Apply.into(),
+        )
+    }
+
+    /// Applies the new paths to the missing clips, the clips that have all their media are
+    /// removed from `missing` to be loaded again
+    fn apply(&self, missing: &mut Vec<bt_save::ClipSave>) -> Vec<bt_save::ClipSave> {
+        for entry in &self.entries {
+            if let Some(path) = &entry.new_path {
+                if let Some(clip) = missing.iter_mut().find(|clip| clip.title == entry.title) {
+                    clip.set_media_path(entry.kind, path.clone());
+                }
+            }
+        }
+
+        let (relinked, still_missing): (Vec<_>, Vec<_>) = std::mem::take(missing)
+            .into_iter()
+            .partition(|clip| clip.missing_media().is_empty());
+        *missing = still_missing;
+        relinked
+    }
+
+    pub(crate) fn update(
+        &mut self,
+        message: RelinkMessage,
+        missing: &mut Vec<bt_save::ClipSave>,
+    ) -> (Command<Message>, bool) {
+        match message {
+            RelinkMessage::Locate(index) => {
+                return (
+                    Command::perform(select_file(self.entries[index].path.clone()), move |p| {
+                        RelinkMessage::Located(index, p).into()
+                    }),
+                    false,
+                )
+            }
+            RelinkMessage::Located(index, path) => {
+                if path.is_some() {
+                    self.entries[index].new_path = path;
+                }
+            }
+            RelinkMessage::Search => {
+                return (
+                    Command::perform(select_folder(), |p| RelinkMessage::SearchIn(p).into()),
+                    false,
+                )
+            }
+            RelinkMessage::SearchIn(None) => (),
+            RelinkMessage::SearchIn(Some(folder)) => {
+                let wanted = self
+                    .entries
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, entry)| entry.new_path.is_none())
+                    .map(|(index, entry)| {
+                        (
+                            index,
+                            Wanted {
+                                path: entry.path.clone(),
+                                hash: entry.hash.clone(),
+                            },
+                        )
+                    })
+                    .collect();
+                self.searching = true;
+                self.error = None;
+                return (
+                    Command::perform(search(folder, wanted), |found| {
+                        RelinkMessage::Found(found).into()
+                    }),
+                    false,
+                );
+            }
+            RelinkMessage::Found(found) => {
+                self.searching = false;
+                if found.is_empty() {
+                    self.error = Some("No missing media was found in this folder".into());
+                }
+                for (index, path) in found {
+                    self.entries[index].new_path = Some(path);
+                }
+            }
+            RelinkMessage::Apply => {
+                let relinked = self.apply(missing);
+                return (
+                    Command::perform(async move { relinked }, Message::Relinked),
+                    true,
+                );
+            }
+        }
+
+        (Command::none(), false)
+    }
+}
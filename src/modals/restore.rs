@@ -0,0 +1,39 @@
+use crate::Message;
+use bt_save::recovery::Recovery;
+use iced::{Column, Element, Text};
+
+/// Offers to restore the unsaved changes left by a session that did not exit cleanly
+pub(crate) struct RestoreState {
+    recovery: Option<Recovery>,
+}
+
+impl RestoreState {
+    pub(crate) fn new(recovery: Recovery) -> Self {
+        Self {
+            recovery: Some(recovery),
+        }
+    }
+
+    pub(crate) fn take(&mut self) -> Option<Recovery> {
+        self.recovery.take()
+    }
+
+    pub(crate) fn view(&mut self) -> (String, Element<Message>, Message) {
+        let project = match self.recovery.as_ref().and_then(|r| r.project.as_ref()) {
+            Some(path) => format!("Project: {}", path.display()),
+            None => "The project was never saved".into(),
+        };
+
+        (
+            "Restore Unsaved Changes".into(),
+            Column::new()
+                .spacing(10)
+                .push(Text::new(
+                    "The last session ended with unsaved changes, do you want to restore them?",
+                ))
+                .push(Text::new(project).size(16))
+                .into(),
+            Message::Restore,
+        )
+    }
+}
@@ -0,0 +1,224 @@
+use super::ModalMessage;
+use crate::{style, Message};
+use bt_save::session::{Scoring, Session};
+use iced::{
+    button, text_input, Button, Color, Column, Command, Container, Element, Length, Row, Text,
+    TextInput,
+};
+
+#[derive(Clone, Debug)]
+pub(crate) enum TeamsMessage {
+    Name(String),
+    Add,
+    Remove(usize),
+    TitlePoints(String),
+    ArtistPoints(String),
+    ClearAnswers,
+    Apply,
+}
+
+fn wrap_teams<T, F: Fn(T) -> TeamsMessage>(f: F) -> impl Fn(T) -> Message {
+    move |v| Message::Modal(ModalMessage::Teams(f(v)))
+}
+
+impl From<TeamsMessage> for Message {
+    fn from(m: TeamsMessage) -> Self {
+        Message::Modal(ModalMessage::Teams(m))
+    }
+}
+
+/// Edits the teams of the session and how many points a clip is worth
+pub(crate) struct TeamsState {
+    session: Session,
+    remove_buttons: Vec<button::State>,
+
+    name_input: text_input::State,
+    name: String,
+    add_button: button::State,
+
+    title_input: text_input::State,
+    title_points: String,
+    artist_input: text_input::State,
+    artist_points: String,
+
+    clear_button: button::State,
+
+    error: Option<String>,
+}
+
+impl TeamsState {
+    pub(crate) fn new(session: Session) -> Self {
+        Self {
+            remove_buttons: session.teams.iter().map(|_| Default::default()).collect(),
+            name_input: Default::default(),
+            name: String::new(),
+            add_button: Default::default(),
+            title_input: Default::default(),
+            title_points: session.scoring.title.to_string(),
+            artist_input: Default::default(),
+            artist_points: session.scoring.artist.to_string(),
+            clear_button: Default::default(),
+            error: None,
+            session,
+        }
+    }
+
+    fn scoring(&self) -> Result<Scoring, String> {
+        Ok(Scoring {
+            title: self
+                .title_points
+                .parse()
+                .map_err(|_| "Points for the title are invalid")?,
+            artist: self
+                .artist_points
+                .parse()
+                .map_err(|_| "Points for the artist are invalid")?,
+        })
+    }
+
+    pub(crate) fn view(&mut self) -> (String, Element<Message>, Message) {
+        let mut form = Column::new().spacing(10);
+
+        if let Some(err) = &self.error {
+            form = form.push(
+                Text::new(err)
+                    .color(Color::from_rgb8(0xff, 0x00, 0x33))
+                    .size(20),
+            );
+        }
+
+        let mut teams = Column::new().spacing(5);
+        if self.session.teams.is_empty() {
+            teams = teams.push(Text::new("No teams yet"));
+        }
+        for (index, (team, remove)) in self
+            .session
+            .teams
+            .iter()
+            .zip(&mut self.remove_buttons)
+            .enumerate()
+        {
+            teams = teams.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(iced::Align::Center)
+                    .push(Text::new(team).width(Length::Fill))
+                    .push(
+                        Button::new(remove, Text::new("Remove"))
+                            .style(style::Button::Destructive)
+                            .on_press(TeamsMessage::Remove(index).into()),
+                    ),
+            );
+        }
+
+        let mut add_button =
+            Button::new(&mut self.add_button, Text::new("Add")).style(style::Button::Primary);
+        if !self.name.trim().is_empty() {
+            add_button = add_button.on_press(TeamsMessage::Add.into());
+        }
+
+        let mut clear_button = Button::new(&mut self.clear_button, Text::new("Clear Scores"))
+            .style(style::Button::Destructive);
+        if !self.session.answers.is_empty() {
+            clear_button = clear_button.on_press(TeamsMessage::ClearAnswers.into());
+        }
+
+        form = form
+            .push(
+                Container::new(teams)
+                    .padding(5)
+                    .width(Length::Fill)
+                    .style(style::BorderContainer),
+            )
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(iced::Align::Center)
+                    .push(
+                        TextInput::new(
+                            &mut self.name_input,
+                            "Team name",
+                            &self.name,
+                            wrap_teams(TeamsMessage::Name),
+                        )
+                        .on_submit(TeamsMessage::Add.into())
+                        .padding(10),
+                    )
+                    .push(add_button),
+            )
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(iced::Align::Center)
+                    .push(Text::new("Points for the title:"))
+                    .push(
+                        TextInput::new(
+                            &mut self.title_input,
+                            "",
+                            &self.title_points,
+                            wrap_teams(TeamsMessage::TitlePoints),
+                        )
+                        .padding(10),
+                    ),
+            )
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(iced::Align::Center)
+                    .push(Text::new("Points for the artist:"))
+                    .push(
+                        TextInput::new(
+                            &mut self.artist_input,
+                            "",
+                            &self.artist_points,
+                            wrap_teams(TeamsMessage::ArtistPoints),
+                        )
+                        .padding(10),
+                    ),
+            )
+            .push(clear_button);
+
+        ("Teams".into(), form.into(), TeamsMessage::Apply.into())
+    }
+
+    pub(crate) fn update<A: FnOnce(Session)>(
+        &mut self,
+        message: TeamsMessage,
+        apply: A,
+    ) -> (Command<Message>, bool) {
+        match message {
+            TeamsMessage::Name(name) => self.name = name,
+            TeamsMessage::Add => {
+                let name = self.name.trim();
+                if name.is_empty() {
+                    return (Command::none(), false);
+                }
+                if self.session.add_team(name.to_owned()) {
+                    self.remove_buttons.push(Default::default());
+                    self.name.clear();
+                    self.error = None;
+                } else {
+                    self.error = Some(format!("The team {} already exists", name));
+                }
+            }
+            TeamsMessage::Remove(index) => {
+                let team = self.session.teams[index].clone();
+                self.session.remove_team(&team);
+                self.remove_buttons.remove(index);
+            }
+            TeamsMessage::TitlePoints(points) => self.title_points = points,
+            TeamsMessage::ArtistPoints(points) => self.artist_points = points,
+            TeamsMessage::ClearAnswers => self.session.answers.clear(),
+            TeamsMessage::Apply => match self.scoring() {
+                Err(e) => self.error = Some(e),
+                Ok(scoring) => {
+                    self.session.scoring = scoring;
+                    apply(std::mem::take(&mut self.session));
+                    return (Command::none(), true);
+                }
+            },
+        }
+
+        (Command::none(), false)
+    }
+}
@@ -0,0 +1,39 @@
+use crate::Message;
+use iced::{Element, Text};
+use std::path::PathBuf;
+
+/// What to do once the unsaved changes are discarded
+#[derive(Clone, Debug)]
+pub(crate) enum DiscardAction {
+    /// Loads the project, or asks which one to load
+    Load(Option<PathBuf>),
+    /// Unpacks the archive and loads its project
+    OpenArchive(PathBuf),
+    Exit,
+}
+
+/// Asks for confirmation before an action that would lose the unsaved changes of the project
+pub(crate) struct UnsavedChangesState {
+    action: DiscardAction,
+}
+
+impl UnsavedChangesState {
+    pub(crate) fn new(action: DiscardAction) -> Self {
+        Self { action }
+    }
+
+    pub(crate) fn view(&mut self) -> (String, Element<Message>, Message) {
+        let text = match self.action {
+            DiscardAction::Load(_) | DiscardAction::OpenArchive(_) => {
+                "The project has unsaved changes, loading another one will discard them."
+            }
+            DiscardAction::Exit => "The project has unsaved changes, they will be lost on exit.",
+        };
+
+        (
+            "Unsaved Changes".into(),
+            Text::new(text).into(),
+            Message::DiscardChanges(self.action.clone()),
+        )
+    }
+}
@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt, fs::File, io::BufReader, path::PathBuf};
+
+/// Number of projects kept in the recent list
+const MAX_RECENT: usize = 10;
+
+/// How the timeline was last exported, to start from there the next time
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub(crate) struct ExportProfile {
+    pub(crate) dir: Option<PathBuf>,
+    /// Used when the chosen file has no extension, `mp4` or one of the audio formats
+    pub(crate) extension: String,
+}
+
+impl Default for ExportProfile {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            extension: "mp4".into(),
+        }
+    }
+}
+
+/// Preferences of the user, kept across sessions
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub(crate) struct Preferences {
+    pub(crate) music_dir: Option<PathBuf>,
+    pub(crate) image_dir: Option<PathBuf>,
+    /// The most recent project is first
+    pub(crate) recent: Vec<PathBuf>,
+    /// Settings of new projects
+    pub(crate) clip_duration: u32,
+    pub(crate) countdown: Option<PathBuf>,
+    pub(crate) export: ExportProfile,
+    /// Port of the remote control server
+    pub(crate) remote_port: u16,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            music_dir: None,
+            image_dir: None,
+            recent: Vec::new(),
+            clip_duration: 30,
+            countdown: None,
+            export: Default::default(),
+            remote_port: 8080,
+        }
+    }
+}
+
+fn path() -> Option<PathBuf> {
+    Some(
+        dirs::config_dir()?
+            .join("blindtest-builder")
+            .join("preferences.json"),
+    )
+}
+
+impl Preferences {
+    /// Reads the preferences, falling back to the defaults when they can't be read
+    pub(crate) fn load() -> Self {
+        let path = match path().filter(|path| path.exists()) {
+            Some(path) => path,
+            None => return Self::default(),
+        };
+
+        let preferences: anyhow::Result<Self> = File::open(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| Ok(serde_json::from_reader(BufReader::new(file))?));
+        match preferences {
+            Ok(mut preferences) => {
+                preferences.recent.retain(|project| project.exists());
+                preferences
+            }
+            Err(e) => {
+                eprintln!("Could not read preferences: {:?}", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub(crate) fn store(&self) {
+        let path = match path() {
+            Some(path) => path,
+            None => return,
+        };
+
+        let res = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| Ok(serde_json::to_writer_pretty(File::create(&path)?, self)?));
+        if let Err(e) = res {
+            eprintln!("Could not write preferences: {:?}", e);
+        }
+    }
+
+    /// Moves the project at the top of the recent list
+    pub(crate) fn add_recent(&mut self, project: PathBuf) {
+        self.recent.retain(|p| *p != project);
+        self.recent.insert(0, project);
+        self.recent.truncate(MAX_RECENT);
+    }
+}
+
+/// A project of the recent list, shown by its file name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RecentProject(pub(crate) PathBuf);
+
+impl fmt::Display for RecentProject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.0.file_name().unwrap_or_default().to_string_lossy();
+        match self.0.parent().and_then(|dir| dir.file_name()) {
+            Some(dir) => write!(f, "{} ({})", name, dir.to_string_lossy()),
+            None => write!(f, "{}", name),
+        }
+    }
+}
@@ -0,0 +1,391 @@
+use crate::{style, Clip, Message};
+use bt_save::{
+    session::{Answer, Session},
+    Challenge,
+};
+use iced::{
+    button,
+    keyboard::{self, KeyCode},
+    Button, Checkbox, Column, Command, Container, Element, Image, Length, Row, Space, Subscription,
+    Text,
+};
+use rodio::{OutputStreamHandle, Sink};
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone)]
+pub(crate) enum PresentationMessage {
+    /// Reveals the answer, or goes to the next clip once it is revealed
+    Next,
+    /// Goes to the next clip without revealing the answer
+    Skip,
+    Back,
+    Replay,
+    Pause,
+    /// Refreshes the countdown
+    Tick,
+    /// What a team found on the current clip
+    Found {
+        team: String,
+        title: bool,
+        artist: bool,
+    },
+    Scoreboard,
+    ExportStandings,
+    StandingsTo(Option<PathBuf>),
+    Quit,
+}
+
+impl From<PresentationMessage> for Message {
+    fn from(m: PresentationMessage) -> Self {
+        Message::Presentation(m)
+    }
+}
+
+const HELP: &str = "Space: reveal / next    Left / Right: previous / next clip    R: replay    \
+                    P: pause    S: scoreboard    Esc: quit";
+
+async fn select_standings() -> Option<PathBuf> {
+    let dialog = native_dialog::FileDialog::new().add_filter("CSV", &["csv"]);
+
+    match dialog.show_save_single_file() {
+        Ok(v) => v.map(|mut path| {
+            if path.extension().is_none() {
+                path.set_extension("csv");
+            }
+            path
+        }),
+        Err(e) => {
+            eprintln!("Error getting path: {:?}", e);
+            None
+        }
+    }
+}
+
+fn scoreboard<'a>(session: &Session) -> Element<'a, Message> {
+    let mut table = Column::new().spacing(10).width(Length::Units(600));
+    for standing in session.standings() {
+        table = table.push(
+            Row::new()
+                .spacing(20)
+                .push(Text::new(format!("{}.", standing.rank)).size(40))
+                .push(Text::new(standing.team).size(40).width(Length::Fill))
+                .push(Text::new(standing.points.to_string()).size(40)),
+        );
+    }
+    table.into()
+}
+
+fn key_message(event: iced_native::Event, _: iced_native::event::Status) -> Option<Message> {
+    let key_code = match event {
+        iced_native::Event::Keyboard(keyboard::Event::KeyPressed { key_code, .. }) => key_code,
+        _ => return None,
+    };
+
+    let message = match key_code {
+        KeyCode::Space | KeyCode::Enter => PresentationMessage::Next,
+        KeyCode::Right => PresentationMessage::Skip,
+        KeyCode::Left => PresentationMessage::Back,
+        KeyCode::R => PresentationMessage::Replay,
+        KeyCode::P => PresentationMessage::Pause,
+        KeyCode::S => PresentationMessage::Scoreboard,
+        KeyCode::Escape => PresentationMessage::Quit,
+        _ => return None,
+    };
+    Some(message.into())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    Playing,
+    Revealed,
+    Done,
+}
+
+/// Runs the blind test live from the timeline, one clip at a time
+pub(crate) struct Presentation {
+    /// The clips of the timeline as they were when the presentation started
+    clips: Vec<Clip>,
+    index: usize,
+    stage: Stage,
+
+    stream_handle: OutputStreamHandle,
+    sink: Sink,
+    duration: u32,
+    challenge: Challenge,
+
+    /// Time the clip played before it was last paused
+    elapsed: Duration,
+    /// When the clip was last started or resumed, `None` while paused
+    resumed: Option<Instant>,
+
+    /// The scoreboard is shown instead of the clip
+    scoreboard: bool,
+    export_button: button::State,
+}
+
+impl Presentation {
+    /// Starts playing the first clip, there must be at least one
+    pub(crate) fn new(
+        clips: Vec<Clip>,
+        duration: u32,
+        challenge: Challenge,
+        stream_handle: &OutputStreamHandle,
+    ) -> Self {
+        let mut presentation = Self {
+            clips,
+            index: 0,
+            stage: Stage::Playing,
+            stream_handle: stream_handle.clone(),
+            sink: Sink::try_new(stream_handle).expect("could not create sink"),
+            duration,
+            challenge,
+            elapsed: Duration::default(),
+            resumed: None,
+            scoreboard: false,
+            export_button: Default::default(),
+        };
+        presentation.play();
+        presentation
+    }
+
+    /// Plays the current clip from its offset
+    fn play(&mut self) {
+        self.sink = Sink::try_new(&self.stream_handle).expect("could not create new sink");
+        match self.clips[self.index].audio(self.duration, self.challenge) {
+            Ok(a) => self.sink.append(a),
+            Err(e) => eprintln!("Could not decode audio: {:?}", e),
+        }
+
+        self.stage = Stage::Playing;
+        self.elapsed = Duration::default();
+        self.resumed = Some(Instant::now());
+    }
+
+    fn next(&mut self) {
+        if self.index + 1 < self.clips.len() {
+            self.index += 1;
+            self.play();
+        } else {
+            self.sink = Sink::try_new(&self.stream_handle).expect("could not create new sink");
+            self.stage = Stage::Done;
+        }
+    }
+
+    fn remaining(&self) -> Duration {
+        let elapsed = self.elapsed + self.resumed.map_or(Duration::default(), |r| r.elapsed());
+        Duration::from_secs(self.duration as u64).saturating_sub(elapsed)
+    }
+
+    pub(crate) fn remote_state(&self) -> bt_remote::PresentationState {
+        let clip = &self.clips[self.index];
+        bt_remote::PresentationState {
+            round: self.index,
+            rounds: self.clips.len(),
+            clip: clip.title.clone(),
+            artist: clip.artist.clone(),
+            revealed: self.stage == Stage::Revealed,
+            paused: self.resumed.is_none(),
+            done: self.stage == Stage::Done,
+            remaining: self.remaining().as_secs_f32().ceil() as u64,
+        }
+    }
+
+    pub(crate) fn subscription(&self) -> Subscription<Message> {
+        let keys = iced_native::subscription::events_with(key_message);
+
+        let counting = self.stage == Stage::Playing
+            && self.resumed.is_some()
+            && self.remaining() > Duration::default();
+        if counting {
+            Subscription::batch(vec![
+                keys,
+                iced::time::every(Duration::from_millis(250))
+                    .map(|_| PresentationMessage::Tick.into()),
+            ])
+        } else {
+            keys
+        }
+    }
+
+    /// Quitting is handled by the application, which drops the presentation
+    pub(crate) fn update(
+        &mut self,
+        message: PresentationMessage,
+        session: &mut Session,
+    ) -> Command<Message> {
+        match message {
+            PresentationMessage::Next => match self.stage {
+                // The music keeps playing while the answer is shown
+                Stage::Playing => self.stage = Stage::Revealed,
+                Stage::Revealed => self.next(),
+                Stage::Done => {}
+            },
+            PresentationMessage::Skip => {
+                if self.stage != Stage::Done {
+                    self.next()
+                }
+            }
+            PresentationMessage::Back => {
+                if self.stage != Stage::Done {
+                    self.index = self.index.saturating_sub(1);
+                }
+                self.play();
+            }
+            PresentationMessage::Replay => {
+                if self.stage != Stage::Done {
+                    self.play()
+                }
+            }
+            PresentationMessage::Pause => {
+                if self.stage == Stage::Done {
+                    return Command::none();
+                }
+                match self.resumed.take() {
+                    Some(resumed) => {
+                        self.elapsed += resumed.elapsed();
+                        self.sink.pause();
+                    }
+                    None => {
+                        self.resumed = Some(Instant::now());
+                        self.sink.play();
+                    }
+                }
+            }
+            PresentationMessage::Found {
+                team,
+                title,
+                artist,
+            } => session.set_answer(Answer {
+                team,
+                round: self.index,
+                clip: self.clips[self.index].title.clone(),
+                title,
+                artist,
+            }),
+            PresentationMessage::Scoreboard => self.scoreboard = !self.scoreboard,
+            PresentationMessage::ExportStandings => {
+                return Command::perform(select_standings(), |p| {
+                    PresentationMessage::StandingsTo(p).into()
+                })
+            }
+            PresentationMessage::StandingsTo(None) => {}
+            PresentationMessage::StandingsTo(Some(path)) => {
+                if let Err(e) = session.export_standings(path) {
+                    eprintln!("Could not export standings: {:?}", e);
+                }
+            }
+            PresentationMessage::Tick | PresentationMessage::Quit => {}
+        }
+
+        Command::none()
+    }
+
+    /// Checkboxes to record what each team found on the current clip
+    fn answers<'a>(&self, session: &Session) -> Element<'a, Message> {
+        let mut row = Row::new().spacing(30);
+        for team in &session.teams {
+            let (title, artist) = session
+                .answer(team, self.index)
+                .map_or((false, false), |answer| (answer.title, answer.artist));
+
+            let title_team = team.clone();
+            let artist_team = team.clone();
+            row = row.push(
+                Column::new()
+                    .spacing(5)
+                    .push(Text::new(team).size(24))
+                    .push(Checkbox::new(title, "Title", move |title| {
+                        PresentationMessage::Found {
+                            team: title_team.clone(),
+                            title,
+                            artist,
+                        }
+                        .into()
+                    }))
+                    .push(Checkbox::new(artist, "Artist", move |artist| {
+                        PresentationMessage::Found {
+                            team: artist_team.clone(),
+                            title,
+                            artist,
+                        }
+                        .into()
+                    })),
+            );
+        }
+        row.into()
+    }
+
+    pub(crate) fn view(&mut self, session: &Session) -> Element<Message> {
+        let clip = &self.clips[self.index];
+
+        let content: Element<_> = match self.stage {
+            _ if self.scoreboard => scoreboard(session),
+            Stage::Playing => {
+                let remaining = self.remaining().as_secs_f32().ceil() as u32;
+                let mut column = Column::new()
+                    .align_items(iced::Align::Center)
+                    .push(Text::new(remaining.to_string()).size(200));
+                if self.resumed.is_none() {
+                    column = column.push(Text::new("Paused").size(40));
+                }
+                column.into()
+            }
+            Stage::Revealed => {
+                let image: Element<_> = match &clip.image {
+                    Some(image) => Image::new(image.clone())
+                        .width(Length::Fill)
+                        .height(Length::Fill)
+                        .into(),
+                    None => clip.image_view(),
+                };
+                let mut column = Column::new()
+                    .spacing(10)
+                    .align_items(iced::Align::Center)
+                    .push(image)
+                    .push(Text::new(&clip.title).size(60));
+                if let Some(artist) = &clip.artist {
+                    column = column.push(Text::new(artist).size(40));
+                }
+                column.push(self.answers(session)).into()
+            }
+            Stage::Done => {
+                let mut column = Column::new()
+                    .spacing(20)
+                    .align_items(iced::Align::Center)
+                    .push(Text::new("The End").size(100));
+                if !session.teams.is_empty() {
+                    column = column.push(scoreboard(session)).push(
+                        Button::new(&mut self.export_button, Text::new("Export Standings"))
+                            .style(style::Button::Primary)
+                            .on_press(PresentationMessage::ExportStandings.into()),
+                    );
+                }
+                column.into()
+            }
+        };
+
+        Container::new(
+            Column::new()
+                .spacing(20)
+                .padding(20)
+                .align_items(iced::Align::Center)
+                .push(Text::new(format!("{} / {}", self.index + 1, self.clips.len())).size(30))
+                .push(
+                    Container::new(content)
+                        .width(Length::Fill)
+                        .height(Length::Fill)
+                        .center_x()
+                        .center_y(),
+                )
+                .push(Space::with_height(Length::Units(10)))
+                .push(Text::new(HELP).size(16)),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(style::Container)
+        .into()
+    }
+}
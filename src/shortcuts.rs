@@ -0,0 +1,66 @@
+use iced::keyboard::{KeyCode, Modifiers};
+
+/// Actions of the builder that can be done from the keyboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Shortcut {
+    Undo,
+    Redo,
+    Save,
+    SaveAs,
+    Load,
+    AddClip,
+    /// Adds an empty entry to the timeline after the selected one
+    AddEntry,
+    /// Edits the clip of the selected entry, or the clip picked in the list
+    EditClip,
+    SelectPrevious,
+    SelectNext,
+    MoveUp,
+    MoveDown,
+    /// Plays or stops the clip of the selected entry
+    TogglePlay,
+    Delete,
+    Help,
+}
+
+/// Keys and what they do, shown in the help
+pub(crate) const BINDINGS: &[(&str, &str)] = &[
+    ("Ctrl+S", "Save"),
+    ("Ctrl+Shift+S", "Save as"),
+    ("Ctrl+O", "Load"),
+    ("Ctrl+Z", "Undo"),
+    ("Ctrl+Shift+Z", "Redo"),
+    ("Ctrl+N", "Add a clip"),
+    ("Ctrl+E", "Edit the clip of the selected entry"),
+    ("Insert", "Add an entry after the selected one"),
+    ("Up / Down", "Select the previous / next entry"),
+    ("Alt+Up / Alt+Down", "Move the selected entry"),
+    ("Space", "Play / stop the selected entry"),
+    ("Delete", "Delete the selected entry"),
+    ("F1", "Show this help"),
+];
+
+/// Ctrl is Cmd on macOS
+pub(crate) fn shortcut(key_code: KeyCode, modifiers: Modifiers) -> Option<Shortcut> {
+    let command = modifiers.is_command_pressed();
+
+    let shortcut = match key_code {
+        KeyCode::Z if command && modifiers.shift => Shortcut::Redo,
+        KeyCode::Z if command => Shortcut::Undo,
+        KeyCode::S if command && modifiers.shift => Shortcut::SaveAs,
+        KeyCode::S if command => Shortcut::Save,
+        KeyCode::O if command => Shortcut::Load,
+        KeyCode::N if command => Shortcut::AddClip,
+        KeyCode::E if command => Shortcut::EditClip,
+        KeyCode::Insert => Shortcut::AddEntry,
+        KeyCode::Up if modifiers.alt => Shortcut::MoveUp,
+        KeyCode::Down if modifiers.alt => Shortcut::MoveDown,
+        KeyCode::Up => Shortcut::SelectPrevious,
+        KeyCode::Down => Shortcut::SelectNext,
+        KeyCode::Space => Shortcut::TogglePlay,
+        KeyCode::Delete => Shortcut::Delete,
+        KeyCode::F1 => Shortcut::Help,
+        _ => return None,
+    };
+    Some(shortcut)
+}
@@ -0,0 +1,353 @@
+use bt_export::media_info;
+use rodio::{buffer::SamplesBuffer, Source};
+use std::{
+    io::{BufReader, Read},
+    path::Path,
+    process::{Child, ChildStdout, Stdio},
+    time::Duration,
+};
+use symphonia::core::{
+    audio::SampleBuffer,
+    codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL},
+    errors::Error as SymphoniaError,
+    formats::{FormatReader, SeekMode, SeekTo},
+    units::{Time, TimeBase},
+};
+
+/// Decodes a media from the disk starting at `offset`, only keeping a packet of samples in memory
+pub(crate) fn open(
+    path: &Path,
+    offset: Duration,
+) -> Result<Box<dyn Source<Item = i16> + Send>, String> {
+    if !bt_export::is_video(path) {
+        match MediaStream::new(path, offset) {
+            Ok(stream) => return Ok(Box::new(stream)),
+            Err(e) => eprintln!("Could not decode {:?}, using ffmpeg: {}", path, e),
+        }
+    }
+
+    Ok(Box::new(PcmStream::new(path, offset)?))
+}
+
+/// Loudest sample of each of the `buckets` parts of a media lasting `duration`, between 0 and 1,
+/// to draw its waveform
+pub(crate) fn peaks(path: &Path, duration: Duration, buckets: usize) -> Result<Vec<f32>, String> {
+    let source = open(path, Duration::from_secs(0))?;
+    let samples = duration.as_secs_f64() * source.sample_rate() as f64 * source.channels() as f64;
+    let per_bucket = ((samples / buckets as f64).ceil() as usize).max(1);
+
+    let mut peaks = vec![0.; buckets];
+    for (index, sample) in source.enumerate() {
+        // The duration is rounded, the last samples can go past it
+        let peak = &mut peaks[(index / per_bucket).min(buckets - 1)];
+        *peak = f32::max(*peak, (sample as f32 / i16::MAX as f32).abs().min(1.));
+    }
+    Ok(peaks)
+}
+
+/// Length of the overlapping parts of the music moved by `time_stretch`
+const STRETCH_FRAME: Duration = Duration::from_millis(40);
+
+/// Plays the source `ratio` times faster without changing its pitch, like the atempo filter of
+/// the export: overlapping windowed parts of the music are taken further apart than they are laid
+/// out. The whole source is read, it is only used for the short preview of a clip.
+pub(crate) fn time_stretch<S: Source<Item = i16>>(source: S, ratio: f32) -> SamplesBuffer<i16> {
+    let (channels, sample_rate) = (source.channels(), source.sample_rate());
+    let samples: Vec<i16> = source.collect();
+    let stretched = stretch(&samples, channels as usize, sample_rate, ratio);
+    SamplesBuffer::new(channels, sample_rate, stretched)
+}
+
+fn stretch(samples: &[i16], channels: usize, sample_rate: u32, ratio: f32) -> Vec<i16> {
+    let frame = ((STRETCH_FRAME.as_secs_f32() * sample_rate as f32) as usize).max(2);
+    let hop_out = frame / 2;
+    let hop_in = hop_out as f32 * ratio;
+    let input_len = samples.len() / channels;
+    let output_len = (input_len as f32 / ratio) as usize;
+
+    let mut output = vec![0f32; output_len * channels];
+    let mut weights = vec![0f32; output_len];
+    for part in 0.. {
+        let (from, to) = ((part as f32 * hop_in) as usize, part * hop_out);
+        if to >= output_len || from >= input_len {
+            break;
+        }
+        let len = frame.min(input_len - from).min(output_len - to);
+        for index in 0..len {
+            // Hann window, the overlapping halves add up to one
+            let weight =
+                0.5 - 0.5 * (2. * std::f32::consts::PI * index as f32 / frame as f32).cos();
+            weights[to + index] += weight;
+            for channel in 0..channels {
+                output[(to + index) * channels + channel] +=
+                    samples[(from + index) * channels + channel] as f32 * weight;
+            }
+        }
+    }
+
+    output
+        .chunks(channels)
+        .zip(weights)
+        .flat_map(|(frame, weight)| {
+            frame.iter().map(move |&sample| {
+                let sample = if weight > 1e-3 {
+                    sample / weight
+                } else {
+                    sample
+                };
+                sample.max(i16::MIN as f32).min(i16::MAX as f32) as i16
+            })
+        })
+        .collect()
+}
+
+/// Media supported by symphonia
+struct MediaStream {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+
+    buffer: Option<SampleBuffer<i16>>,
+    position: usize,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl MediaStream {
+    fn new(path: &Path, offset: Duration) -> Result<Self, SymphoniaError> {
+        let format = media_info::open_format(path)?;
+
+        let track = format
+            .tracks()
+            .iter()
+            .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or(SymphoniaError::Unsupported("no audio track"))?;
+        let track_id = track.id;
+        let params = track.codec_params.clone();
+
+        let sample_rate = params
+            .sample_rate
+            .ok_or(SymphoniaError::Unsupported("unknown sample rate"))?;
+        let channels = params
+            .channels
+            .ok_or(SymphoniaError::Unsupported("unknown channels"))?
+            .count() as u16;
+        let decoder = symphonia::default::get_codecs().make(&params, &DecoderOptions::default())?;
+
+        let mut stream = Self {
+            format,
+            decoder,
+            track_id,
+            buffer: None,
+            position: 0,
+            channels,
+            sample_rate,
+        };
+
+        // The seek lands on the packet containing the offset, the start of the packet is skipped
+        let mut skip = 0;
+        if offset > Duration::from_secs(0) {
+            let seeked = stream.format.seek(
+                SeekMode::Accurate,
+                SeekTo::Time {
+                    time: Time::from(offset),
+                    track_id: Some(track_id),
+                },
+            )?;
+            let time_base = params
+                .time_base
+                .unwrap_or_else(|| TimeBase::new(1, sample_rate));
+            let time = time_base.calc_time(seeked.required_ts.saturating_sub(seeked.actual_ts));
+            skip = ((time.seconds as f64 + time.frac) * sample_rate as f64) as usize
+                * channels as usize;
+        }
+
+        stream.decode_packet()?;
+        stream.position = skip.min(stream.buffer_len());
+        if stream.position >= stream.buffer_len() {
+            stream.next_packet();
+        }
+
+        Ok(stream)
+    }
+
+    fn buffer_len(&self) -> usize {
+        self.buffer.as_ref().map(|b| b.len()).unwrap_or(0)
+    }
+
+    /// Moves to the next packet, dropping the buffer at the end of the media or on an error
+    fn next_packet(&mut self) {
+        if let Err(e) = self.decode_packet() {
+            if !matches!(&e, SymphoniaError::IoError(e) if e.kind() == std::io::ErrorKind::UnexpectedEof)
+            {
+                eprintln!("Error decoding music: {}", e);
+            }
+            self.buffer = None;
+            self.position = 0;
+        }
+    }
+
+    /// Replaces the buffer with the next packet of the track
+    fn decode_packet(&mut self) -> Result<(), SymphoniaError> {
+        loop {
+            let packet = self.format.next_packet()?;
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+
+            let decoded = match self.decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                // A corrupted packet is skipped, like most players do
+                Err(SymphoniaError::DecodeError(_)) => continue,
+                Err(e) => return Err(e),
+            };
+            let spec = *decoded.spec();
+            let needed = decoded.capacity() * spec.channels.count();
+
+            // Not going through a method, `decoded` borrows the decoder
+            if self.buffer.as_ref().map(|b| b.capacity()).unwrap_or(0) < needed {
+                self.buffer = Some(SampleBuffer::new(decoded.capacity() as u64, spec));
+            }
+            let buffer = self.buffer.as_mut().expect("buffer was just allocated");
+            buffer.copy_interleaved_ref(decoded);
+
+            self.position = 0;
+            self.channels = spec.channels.count() as u16;
+            self.sample_rate = spec.rate;
+            if !buffer.is_empty() {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl Iterator for MediaStream {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        let sample = *self.buffer.as_ref()?.samples().get(self.position)?;
+        self.position += 1;
+        // Decoded right away so that the frame is never empty before the end of the media, rodio
+        // stops a source at an empty frame
+        if self.position >= self.buffer_len() {
+            self.next_packet();
+        }
+        Some(sample)
+    }
+}
+
+impl Source for MediaStream {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some(self.buffer_len() - self.position)
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Media decoded by an ffmpeg process, for videos and formats symphonia does not support
+struct PcmStream {
+    child: Child,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl PcmStream {
+    fn new(path: &Path, offset: Duration) -> Result<Self, String> {
+        let mut child = bt_export::extract_audio_command(path, offset)
+            .stdin(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Could not launch ffmpeg: {}", e))?;
+        let stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+
+        Ok(Self { child, stdout })
+    }
+}
+
+impl Drop for PcmStream {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl Iterator for PcmStream {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        let mut sample = [0; 2];
+        self.stdout.read_exact(&mut sample).ok()?;
+        Some(i16::from_le_bytes(sample))
+    }
+}
+
+impl Source for PcmStream {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        bt_export::EXTRACTED_CHANNELS
+    }
+
+    fn sample_rate(&self) -> u32 {
+        bt_export::EXTRACTED_SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::source::UniformSourceIterator;
+
+    /// Writes a mono 16 bits wav of `frames` samples
+    fn write_wav(path: &Path, frames: u32, sample_rate: u32) {
+        let data_len = frames * 2;
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        // PCM, one channel
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&sample_rate.to_le_bytes());
+        wav.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        for frame in 0..frames {
+            wav.extend_from_slice(&((frame % 1000) as i16).to_le_bytes());
+        }
+        std::fs::write(path, wav).unwrap();
+    }
+
+    #[test]
+    fn play_every_packet() {
+        let path = std::env::temp_dir().join(format!("bt-stream-{}.wav", std::process::id()));
+        write_wav(&path, 44100 * 3, 44100);
+
+        // The sink converts the sources like this, going through all the packets of the file
+        let stream = MediaStream::new(&path, Duration::from_secs(0)).unwrap();
+        let full = UniformSourceIterator::<_, i16>::new(stream, 2, 44100).count();
+        let stream = MediaStream::new(&path, Duration::from_secs(1)).unwrap();
+        let offset = UniformSourceIterator::<_, i16>::new(stream, 2, 44100).count();
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(full, 44100 * 3 * 2);
+        assert_eq!(offset, 44100 * 2 * 2);
+    }
+}
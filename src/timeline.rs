@@ -1,10 +1,15 @@
 use crate::{
+    drag::DragArea,
     export::{Export, Progress},
+    history::Edit,
+    preferences::ExportProfile,
     style, Clip, Message,
 };
+use bt_save::Challenge;
 use iced::{
-    button, pick_list, scrollable, Button, Column, Command, Container, Element, Image, Length,
-    PickList, ProgressBar, Row, Rule, Scrollable, Space, Subscription, Text,
+    button, pick_list, scrollable, text_input, Button, Checkbox, Column, Command, Container,
+    Element, Length, PickList, ProgressBar, Row, Rule, Scrollable, Space, Subscription, Text,
+    TextInput,
 };
 use itertools::Itertools;
 use rodio::{OutputStreamHandle, Sink};
@@ -27,6 +32,22 @@ pub(crate) enum TimelineMessage {
     Save,
     SaveTo(Option<PathBuf>),
     ExportProgress(crate::export::Progress),
+    SelectPrevious,
+    SelectNext,
+    /// Adds an empty entry after the selected one
+    AddAfterSelected,
+    SelectedAction(TimelineAction),
+    /// Plays or stops the clip of the selected entry
+    ToggleSelected,
+    DragStart(usize),
+    /// The dragged entries are released over this one
+    Drop(usize),
+    /// The dragged entries are released outside of the timeline
+    DragEnd,
+    MovePosition(String),
+    MoveMarked,
+    DeleteMarked,
+    ClearMarks,
 }
 
 #[derive(Debug, Clone)]
@@ -36,6 +57,8 @@ pub(crate) enum TimelineClipMessage {
     Action(TimelineAction),
     Play,
     Stop,
+    /// Adds the entry to the entries moved or deleted together
+    Mark(bool),
 }
 
 #[derive(Clone, Debug)]
@@ -43,6 +66,10 @@ pub(crate) enum TimelineAction {
     Up,
     Down,
     Delete,
+    /// Adds an empty entry before this one
+    InsertBefore,
+    /// Puts the clip in this entry
+    Set(String),
 }
 
 impl From<TimelineMessage> for Message {
@@ -57,7 +84,7 @@ impl From<TimelineAction> for TimelineClipMessage {
     }
 }
 
-fn timeline_clip_msg(index: usize, msg: TimelineClipMessage) -> Message {
+pub(crate) fn timeline_clip_msg(index: usize, msg: TimelineClipMessage) -> Message {
     TimelineMessage::TimelineClip(index, msg).into()
 }
 
@@ -78,6 +105,8 @@ struct TimelineClip {
     up_button: button::State,
     down_button: button::State,
     delete: button::State,
+    insert_button: button::State,
+    marked: bool,
 }
 
 impl TimelineClip {
@@ -90,6 +119,8 @@ impl TimelineClip {
             up_button: Default::default(),
             down_button: Default::default(),
             delete: Default::default(),
+            insert_button: Default::default(),
+            marked: false,
             audio_button: Default::default(),
             playing: false,
             sink: Sink::try_new(stream_handle).expect("could not build sink"),
@@ -101,15 +132,21 @@ impl TimelineClip {
         msg: TimelineClipMessage,
         clip: Option<&Clip>,
         duration: u32,
+        challenge: Challenge,
         stream_handle: &OutputStreamHandle,
     ) -> (Command<Message>, Option<TimelineAction>) {
         match msg {
             TimelineClipMessage::SelectedClip(c) => self.selected = Some(c),
-            TimelineClipMessage::ValidateClip => self.clip = self.selected.clone(),
+            TimelineClipMessage::ValidateClip => {
+                return (
+                    Command::none(),
+                    self.selected.clone().map(TimelineAction::Set),
+                )
+            }
             TimelineClipMessage::Action(a) => return (Command::none(), Some(a)),
             TimelineClipMessage::Play => {
                 let clip = clip.expect("clip must be present in this command");
-                match clip.audio(duration) {
+                match clip.audio(duration, challenge) {
                     Ok(a) => self.sink.append(a),
                     Err(e) => eprintln!("Could not decode audio: {:?}", e),
                 };
@@ -119,6 +156,7 @@ impl TimelineClip {
                 self.sink = Sink::try_new(stream_handle).expect("could not create new sink");
                 self.playing = false
             }
+            TimelineClipMessage::Mark(marked) => self.marked = marked,
         }
 
         (Command::none(), None)
@@ -127,11 +165,12 @@ impl TimelineClip {
     fn view(
         &mut self,
         clips: &HashMap<String, Clip>,
+        missing: &[bt_save::ClipSave],
         index: usize,
         len: usize,
     ) -> Element<Message> {
         if let Some(clip) = &self.clip {
-            if !clips.contains_key(clip) {
+            if !clips.contains_key(clip) && !missing.iter().any(|m| &m.title == clip) {
                 self.clip = None;
             }
         }
@@ -141,9 +180,14 @@ impl TimelineClip {
             }
         }
 
-        let content: Element<_> = match &self.clip {
-            Some(clip) => {
-                let clip_data = clips.get(clip).expect("just checked");
+        let content: Element<_> = match self.clip.as_ref().map(|clip| (clip, clips.get(clip))) {
+            Some((clip, None)) => Column::new()
+                .push(Text::new(clip))
+                .push(Text::new("Missing media, relink it to play this clip"))
+                .spacing(5)
+                .align_items(iced::Align::Center)
+                .into(),
+            Some((clip, Some(clip_data))) => {
                 let audio_button = Button::new(
                     &mut self.audio_button,
                     Text::new(if self.playing {
@@ -174,7 +218,7 @@ impl TimelineClip {
                     .push(
                         Container::new(
                             Row::new()
-                                .push(Image::new(clip_data.image.clone()))
+                                .push(clip_data.image_view())
                                 .push(controls)
                                 .spacing(10)
                                 .align_items(iced::Align::Center),
@@ -233,13 +277,24 @@ impl TimelineClip {
         let controls = Row::new()
             .spacing(10)
             .align_items(iced::Align::Center)
+            .push(Checkbox::new(self.marked, "Select", move |marked| {
+                timeline_clip_msg(index, TimelineClipMessage::Mark(marked))
+            }))
             .push(up)
             .push(
                 Button::new(&mut self.delete, Text::new("Delete"))
                     .style(style::Button::Destructive)
                     .on_press(timeline_clip_msg(index, TimelineAction::Delete.into())),
             )
-            .push(down);
+            .push(down)
+            .push(
+                Button::new(&mut self.insert_button, Text::new("Insert Before"))
+                    .style(style::Button::Primary)
+                    .on_press(timeline_clip_msg(
+                        index,
+                        TimelineAction::InsertBefore.into(),
+                    )),
+            );
 
         Container::new(
             Column::new()
@@ -256,12 +311,22 @@ impl TimelineClip {
     }
 }
 
-async fn select_export() -> Option<PathBuf> {
-    let dialog = native_dialog::FileDialog::new().add_filter("MP4", &["mp4"]);
+async fn select_export(profile: ExportProfile) -> Option<PathBuf> {
+    let mut dialog = native_dialog::FileDialog::new()
+        .add_filter("MP4", &["mp4"])
+        .add_filter("Audio only", bt_export::AudioFormat::EXTENSIONS);
+    if let Some(dir) = profile.dir.as_deref().filter(|dir| dir.exists()) {
+        dialog = dialog.set_location(dir);
+    }
     let res = dialog.show_save_single_file();
 
     match res {
-        Ok(v) => v,
+        Ok(v) => v.map(|mut path| {
+            if path.extension().is_none() {
+                path.set_extension(&profile.extension);
+            }
+            path
+        }),
         Err(e) => {
             eprintln!("Error getting path: {:?}", e);
             None
@@ -271,6 +336,15 @@ async fn select_export() -> Option<PathBuf> {
 
 pub(crate) struct Timeline {
     clips: VecDeque<TimelineClip>,
+    /// Entry the keyboard shortcuts act on
+    selected: Option<usize>,
+    /// Entry being dragged, along with the marked ones if it is marked
+    dragging: Option<usize>,
+    position_input: text_input::State,
+    position: String,
+    move_button: button::State,
+    delete_marked_button: button::State,
+    clear_marks_button: button::State,
 
     start_button: button::State,
     end_button: button::State,
@@ -278,6 +352,8 @@ pub(crate) struct Timeline {
     playing: bool,
     audio_button: button::State,
     export_button: button::State,
+    playlist_button: button::State,
+    present_button: button::State,
 
     export: Option<PathBuf>,
     progress: f32,
@@ -290,8 +366,7 @@ impl Timeline {
     pub(crate) fn subscription(
         &self,
         clips: &HashMap<String, Clip>,
-        countdown: Option<PathBuf>,
-        duration: u32,
+        settings: bt_save::Settings,
     ) -> Subscription<Message> {
         match &self.export {
             Some(path) => {
@@ -302,14 +377,7 @@ impl Timeline {
                     .map(|s| -> &str { s })
                     .collect();
                 Subscription::from_recipe(
-                    Export::new(
-                        path.clone(),
-                        &items,
-                        clips,
-                        countdown.expect("Countdown was not provided"),
-                        duration,
-                    )
-                    .expect("Export failed"),
+                    Export::new(path.clone(), &items, clips, settings).expect("Export failed"),
                 )
                 .map(|p| Message::Timeline(TimelineMessage::ExportProgress(p)))
             }
@@ -317,11 +385,10 @@ impl Timeline {
         }
     }
 
-    fn play_all(&self, clips: &HashMap<String, Clip>, duration: u32) {
+    fn play_all(&self, clips: &HashMap<String, Clip>, duration: u32, challenge: Challenge) {
         for clip in &self.clips {
-            if let Some(clip) = &clip.clip {
-                let clip_data = clips.get(clip).expect("clip not present");
-                match clip_data.audio(duration) {
+            if let Some(clip_data) = clip.clip.as_ref().and_then(|clip| clips.get(clip)) {
+                match clip_data.audio(duration, challenge) {
                     Ok(a) => self.sink.append(a),
                     Err(e) => eprintln!("Could not decode audio: {:?}", e),
                 };
@@ -345,14 +412,153 @@ impl Timeline {
             .collect();
     }
 
+    pub(crate) fn len(&self) -> usize {
+        self.clips.len()
+    }
+
+    /// The selection can be past the end after entries were removed
+    fn selected(&self) -> Option<usize> {
+        self.selected.filter(|&index| index < self.clips.len())
+    }
+
+    /// The clip of the selected entry
+    pub(crate) fn selected_clip(&self) -> Option<&str> {
+        self.selected()
+            .and_then(|index| self.clips[index].clip.as_deref())
+    }
+
+    fn select(&mut self, index: usize) {
+        if self.clips.is_empty() {
+            self.selected = None;
+            return;
+        }
+
+        let index = index.min(self.clips.len() - 1);
+        self.selected = Some(index);
+        if self.clips.len() > 1 {
+            self.scroll_data
+                .scroll_to_percentage(index as f32 / (self.clips.len() as f32 - 1.0));
+        }
+    }
+
+    /// Puts the entries in a new order, the entry at `i` being the one that was at `order[i]`
+    pub(crate) fn reorder(&mut self, order: &[usize]) {
+        self.clips = reordered(std::mem::take(&mut self.clips), order);
+    }
+
+    fn marked(&self) -> Vec<usize> {
+        self.clips
+            .iter()
+            .enumerate()
+            .filter(|(_, clip)| clip.marked)
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Moves the entries as a block, which starts at `to` once they are moved
+    fn move_entries(&mut self, moved: &[usize], to: usize) -> Option<Edit> {
+        let (order, to) = move_order(self.clips.len(), moved, to);
+        self.selected = Some(to);
+        if order.iter().enumerate().all(|(new, &old)| new == old) {
+            None
+        } else {
+            Some(Edit::TimelineReorder(order))
+        }
+    }
+
+    /// The whole timeline is playing
+    pub(crate) fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Stops the whole timeline and the entries being played on their own
+    pub(crate) fn stop(&mut self, stream_handle: &OutputStreamHandle) {
+        if self.playing {
+            self.sink = Sink::try_new(stream_handle).expect("could not create new sink");
+            self.playing = false;
+        }
+        for clip in self.clips.iter_mut().filter(|clip| clip.playing) {
+            clip.sink = Sink::try_new(stream_handle).expect("could not create new sink");
+            clip.playing = false;
+        }
+    }
+
+    /// Entries being played on their own
+    pub(crate) fn playing_clips(&self) -> Vec<usize> {
+        self.clips
+            .iter()
+            .enumerate()
+            .filter(|(_, clip)| clip.playing)
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// The entry has a clip that is loaded, and can be played
+    pub(crate) fn playable(&self, index: usize, clips: &HashMap<String, Clip>) -> bool {
+        matches!(
+            self.clips.get(index).and_then(|clip| clip.clip.as_ref()),
+            Some(clip) if clips.contains_key(clip)
+        )
+    }
+
+    pub(crate) fn insert(
+        &mut self,
+        index: usize,
+        clip: Option<String>,
+        stream_handle: &OutputStreamHandle,
+    ) {
+        let mut tclip = TimelineClip::new(stream_handle);
+        tclip.selected = clip.clone();
+        tclip.clip = clip;
+        self.clips.insert(index, tclip);
+    }
+
+    pub(crate) fn remove(&mut self, index: usize) -> Option<String> {
+        self.clips.remove(index).and_then(|clip| clip.clip)
+    }
+
+    pub(crate) fn swap(&mut self, a: usize, b: usize) {
+        self.clips.swap(a, b)
+    }
+
+    /// Changes the clip of an entry, returning the previous one
+    pub(crate) fn set(&mut self, index: usize, clip: Option<String>) -> Option<String> {
+        let tclip = &mut self.clips[index];
+        tclip.selected = clip.clone();
+        std::mem::replace(&mut tclip.clip, clip)
+    }
+
+    /// Empties the entries using a clip, returning their indices
+    pub(crate) fn clear_clip(&mut self, clip: &str) -> Vec<usize> {
+        self.clips
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, tclip)| tclip.clip.as_deref() == Some(clip))
+            .map(|(index, tclip)| {
+                tclip.clip = None;
+                tclip.selected = None;
+                index
+            })
+            .collect()
+    }
+
     pub(crate) fn new(stream_handle: &OutputStreamHandle) -> Self {
         Timeline {
             clips: VecDeque::new(),
+            selected: None,
+            dragging: None,
+            position_input: Default::default(),
+            position: String::new(),
+            move_button: Default::default(),
+            delete_marked_button: Default::default(),
+            clear_marks_button: Default::default(),
             scroll_data: Default::default(),
             start_button: Default::default(),
             end_button: Default::default(),
             audio_button: Default::default(),
             export_button: Default::default(),
+            playlist_button: Default::default(),
+            present_button: Default::default(),
             sink: Sink::try_new(stream_handle).expect("could not create sink"),
             playing: false,
             export: None,
@@ -367,45 +573,70 @@ impl Timeline {
         clips: &HashMap<String, Clip>,
         stream_handle: &OutputStreamHandle,
         duration: u32,
-    ) -> Command<Message> {
+        challenge: Challenge,
+        profile: &mut ExportProfile,
+    ) -> (Command<Message>, Option<Edit>) {
         match message {
             TimelineMessage::AddStart => {
                 self.scroll_data.scroll_to_percentage(0.);
-                self.clips.push_front(TimelineClip::new(stream_handle))
+                return (
+                    Command::none(),
+                    Some(Edit::TimelineInsert {
+                        index: 0,
+                        clip: None,
+                    }),
+                );
             }
             TimelineMessage::AddEnd => {
                 self.scroll_data.scroll_to_percentage(1.);
-                self.clips.push_back(TimelineClip::new(stream_handle))
+                return (
+                    Command::none(),
+                    Some(Edit::TimelineInsert {
+                        index: self.clips.len(),
+                        clip: None,
+                    }),
+                );
             }
             TimelineMessage::TimelineClip(index, msg) => {
+                self.selected = Some(index);
                 let clip = self.clips[index]
                     .clip
                     .as_ref()
-                    .map(|clip| clips.get(clip).expect("clip not present"));
-                let (cmd, action) = self.clips[index].update(msg, clip, duration, stream_handle);
-                if let Some(action) = action {
-                    match action {
-                        TimelineAction::Up => {
-                            self.scroll_data.scroll_to_percentage(
-                                (index - 1) as f32 / (self.clips.len() as f32 - 1.0),
-                            );
-                            self.clips.swap(index, index - 1)
-                        }
-                        TimelineAction::Down => {
-                            self.scroll_data.scroll_to_percentage(
-                                (index + 1) as f32 / (self.clips.len() as f32 - 1.0),
-                            );
-                            self.clips.swap(index, index + 1)
-                        }
-                        TimelineAction::Delete => {
-                            self.clips.remove(index);
+                    .and_then(|clip| clips.get(clip));
+                let (cmd, action) =
+                    self.clips[index].update(msg, clip, duration, challenge, stream_handle);
+                let edit = action.map(|action| match action {
+                    TimelineAction::Up => {
+                        self.scroll_data.scroll_to_percentage(
+                            (index - 1) as f32 / (self.clips.len() as f32 - 1.0),
+                        );
+                        self.selected = Some(index - 1);
+                        Edit::TimelineSwap(index, index - 1)
+                    }
+                    TimelineAction::Down => {
+                        self.scroll_data.scroll_to_percentage(
+                            (index + 1) as f32 / (self.clips.len() as f32 - 1.0),
+                        );
+                        self.selected = Some(index + 1);
+                        Edit::TimelineSwap(index, index + 1)
+                    }
+                    // The next entry takes the place of the removed one
+                    TimelineAction::Delete => {
+                        if index + 1 == self.clips.len() {
+                            self.selected = index.checked_sub(1);
                         }
+                        Edit::TimelineRemove(index)
                     }
-                }
-                return cmd;
+                    TimelineAction::InsertBefore => Edit::TimelineInsert { index, clip: None },
+                    TimelineAction::Set(clip) => Edit::TimelineSet {
+                        index,
+                        clip: Some(clip),
+                    },
+                });
+                return (cmd, edit);
             }
             TimelineMessage::Play => {
-                self.play_all(clips, duration);
+                self.play_all(clips, duration, challenge);
                 self.playing = true;
             }
             TimelineMessage::Stop => {
@@ -413,28 +644,151 @@ impl Timeline {
                 self.playing = false;
             }
             TimelineMessage::Save => {
-                return Command::perform(select_export(), |p| {
-                    Message::Timeline(TimelineMessage::SaveTo(p))
-                })
+                return (
+                    Command::perform(select_export(profile.clone()), |p| {
+                        Message::Timeline(TimelineMessage::SaveTo(p))
+                    }),
+                    None,
+                )
             }
             TimelineMessage::SaveTo(path) => {
+                if let Some(path) = &path {
+                    profile.dir = path.parent().map(ToOwned::to_owned);
+                    if let Some(extension) = path.extension() {
+                        profile.extension = extension.to_string_lossy().into_owned();
+                    }
+                }
                 self.export = path;
                 self.progress_max = (25 * duration as usize * self.clips.len()) as f32;
             }
             TimelineMessage::ExportProgress(p) => match p {
                 Progress::Started => {}
                 Progress::Frame(f) => self.progress = f as _,
+                // Audio exports have no frames, count them as if they were rendered at 25 fps
+                Progress::Time(t) => self.progress = t.as_secs_f32() * 25.,
                 Progress::Done => self.export = None,
                 Progress::Error(e) => println!("Error in export: {}", e),
             },
+            TimelineMessage::SelectPrevious => {
+                self.select(self.selected().map_or(0, |index| index.saturating_sub(1)))
+            }
+            TimelineMessage::SelectNext => {
+                self.select(self.selected().map_or(0, |index| index + 1))
+            }
+            TimelineMessage::AddAfterSelected => {
+                let index = self.selected().map_or(self.clips.len(), |index| index + 1);
+                self.selected = Some(index);
+                self.scroll_data
+                    .scroll_to_percentage(index as f32 / self.clips.len().max(1) as f32);
+                return (
+                    Command::none(),
+                    Some(Edit::TimelineInsert { index, clip: None }),
+                );
+            }
+            TimelineMessage::SelectedAction(action) => {
+                let index = match self.selected() {
+                    Some(index) => index,
+                    None => return (Command::none(), None),
+                };
+                let possible = match action {
+                    TimelineAction::Up => index > 0,
+                    TimelineAction::Down => index + 1 < self.clips.len(),
+                    TimelineAction::Delete
+                    | TimelineAction::InsertBefore
+                    | TimelineAction::Set(_) => true,
+                };
+                if possible {
+                    return self.update(
+                        TimelineMessage::TimelineClip(index, action.into()),
+                        clips,
+                        stream_handle,
+                        duration,
+                        challenge,
+                        profile,
+                    );
+                }
+            }
+            TimelineMessage::DragStart(index) => self.dragging = Some(index),
+            TimelineMessage::Drop(target) => {
+                let len = self.clips.len();
+                let from = match self.dragging.take() {
+                    Some(from) if from < len && target < len => from,
+                    _ => return (Command::none(), None),
+                };
+                // Pressing and releasing the mouse on an entry selects it
+                if from == target {
+                    self.selected = Some(target);
+                    return (Command::none(), None);
+                }
+
+                let moved = if self.clips[from].marked {
+                    self.marked()
+                } else {
+                    vec![from]
+                };
+                if moved.contains(&target) {
+                    return (Command::none(), None);
+                }
+                let to = drop_position(&moved, target);
+                return (Command::none(), self.move_entries(&moved, to));
+            }
+            TimelineMessage::DragEnd => self.dragging = None,
+            TimelineMessage::MovePosition(position) => self.position = position,
+            TimelineMessage::MoveMarked => {
+                let moved = self.marked();
+                if let Ok(position) = self.position.trim().parse::<usize>() {
+                    if position > 0 && !moved.is_empty() {
+                        return (Command::none(), self.move_entries(&moved, position - 1));
+                    }
+                }
+            }
+            TimelineMessage::DeleteMarked => {
+                let edits: Vec<_> = self
+                    .marked()
+                    .into_iter()
+                    .rev()
+                    .map(Edit::TimelineRemove)
+                    .collect();
+                if !edits.is_empty() {
+                    self.selected = None;
+                    return (Command::none(), Some(Edit::Batch(edits)));
+                }
+            }
+            TimelineMessage::ClearMarks => {
+                for clip in &mut self.clips {
+                    clip.marked = false;
+                }
+            }
+            TimelineMessage::ToggleSelected => {
+                let index = match self.selected() {
+                    Some(index) => index,
+                    None => return (Command::none(), None),
+                };
+                let message = if self.clips[index].playing {
+                    TimelineClipMessage::Stop
+                } else if self.playable(index, clips) {
+                    TimelineClipMessage::Play
+                } else {
+                    return (Command::none(), None);
+                };
+                return self.update(
+                    TimelineMessage::TimelineClip(index, message),
+                    clips,
+                    stream_handle,
+                    duration,
+                    challenge,
+                    profile,
+                );
+            }
         }
 
-        Command::none()
+        (Command::none(), None)
     }
 
     pub(crate) fn view(
         &mut self,
         clips: &HashMap<String, Clip>,
+        missing: &[bt_save::ClipSave],
         clip_duration: u32,
     ) -> Element<Message> {
         let mut scrollable = Scrollable::new(&mut self.scroll_data)
@@ -446,15 +800,37 @@ impl Timeline {
             .spacing(10);
 
         let len = self.clips.len();
+        let selected = self.selected();
+        let marked = self.marked().len();
+        let dragging = self.dragging.is_some();
         for (index, clip) in self.clips.iter_mut().enumerate() {
-            scrollable = scrollable.push(Element::from(
-                Container::new(clip.view(clips, index, len))
-                    .style(style::BorderContainer)
-                    .width(iced::Length::Fill)
-                    .max_height(CLIP_HEIGHT)
-                    .padding(10)
-                    .center_x(),
-            ));
+            let is_marked = clip.marked;
+            let title = clip.clip.clone();
+            let container = Container::new(clip.view(clips, missing, index, len))
+                .width(iced::Length::Fill)
+                .max_height(CLIP_HEIGHT)
+                .padding(10)
+                .center_x();
+            let container = if selected == Some(index) {
+                container.style(style::SelectedContainer)
+            } else if is_marked {
+                container.style(style::MarkedContainer)
+            } else {
+                container.style(style::BorderContainer)
+            };
+
+            let mut area =
+                DragArea::new(container).on_press(TimelineMessage::DragStart(index).into());
+            if dragging {
+                area = area.on_release(TimelineMessage::Drop(index).into());
+            }
+            if let Some(title) = title {
+                area = area.on_file_drop(move |path| Message::FileDropped {
+                    path,
+                    clip: Some(title.clone()),
+                });
+            }
+            scrollable = scrollable.push(area);
         }
 
         let audio_button = Button::new(
@@ -474,9 +850,24 @@ impl Timeline {
 
         let mut export_button =
             Button::new(&mut self.export_button, Text::new("Export")).style(style::Button::Primary);
-        if self.export.is_none() {
+        // Clips with missing media can't be exported
+        let complete = self
+            .clips
+            .iter()
+            .filter_map(|clip| clip.clip.as_ref())
+            .all(|clip| clips.contains_key(clip));
+        if self.export.is_none() && complete {
             export_button = export_button.on_press(TimelineMessage::Save.into());
         }
+        let mut playlist_button =
+            Button::new(&mut self.playlist_button, Text::new("Export Playlist"))
+                .style(style::Button::Primary);
+        let mut present_button = Button::new(&mut self.present_button, Text::new("Present"))
+            .style(style::Button::Primary);
+        if complete && len > 0 {
+            playlist_button = playlist_button.on_press(Message::ExportPlaylist);
+            present_button = present_button.on_press(Message::Present);
+        }
 
         let mut column = Column::new();
         if len > 0 {
@@ -499,6 +890,51 @@ impl Timeline {
             )
         }
 
+        if let Some(from) = self.dragging {
+            let count = match self.clips.get(from) {
+                Some(clip) if clip.marked => marked,
+                _ => 1,
+            };
+            column = column.push(Text::new(format!(
+                "Release over an entry to move {} entr{} there",
+                count,
+                if count > 1 { "ies" } else { "y" }
+            )));
+        }
+        if marked > 0 {
+            let mut move_button = Button::new(&mut self.move_button, Text::new("Move to Position"))
+                .style(style::Button::Primary);
+            if matches!(self.position.trim().parse::<usize>(), Ok(position) if position > 0) {
+                move_button = move_button.on_press(TimelineMessage::MoveMarked.into());
+            }
+
+            column = column.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(iced::Align::Center)
+                    .push(Text::new(format!("{} selected", marked)))
+                    .push(
+                        TextInput::new(&mut self.position_input, "Position", &self.position, |p| {
+                            TimelineMessage::MovePosition(p).into()
+                        })
+                        .on_submit(TimelineMessage::MoveMarked.into())
+                        .width(Length::Units(100))
+                        .padding(5),
+                    )
+                    .push(move_button)
+                    .push(
+                        Button::new(&mut self.delete_marked_button, Text::new("Delete Selected"))
+                            .style(style::Button::Destructive)
+                            .on_press(TimelineMessage::DeleteMarked.into()),
+                    )
+                    .push(
+                        Button::new(&mut self.clear_marks_button, Text::new("Clear Selection"))
+                            .style(style::Button::Primary)
+                            .on_press(TimelineMessage::ClearMarks.into()),
+                    ),
+            );
+        }
+
         column = column
             .push(
                 Row::new()
@@ -510,7 +946,9 @@ impl Timeline {
                             .on_press(TimelineMessage::AddStart.into()),
                     )
                     .push(audio_button)
-                    .push(export_button),
+                    .push(export_button)
+                    .push(playlist_button)
+                    .push(present_button),
             )
             .push(scrollable)
             .push(
@@ -521,6 +959,90 @@ impl Timeline {
             .align_items(iced::Align::Center)
             .spacing(10);
 
-        column.into()
+        // The entries are dropped back where they were when released elsewhere
+        if dragging {
+            DragArea::new(column)
+                .on_release_anywhere(TimelineMessage::DragEnd.into())
+                .into()
+        } else {
+            column.into()
+        }
+    }
+}
+
+/// The entries in a new order, the entry at `i` being the one that was at `order[i]`
+pub(crate) fn reordered<T, C: FromIterator<T>>(
+    entries: impl IntoIterator<Item = T>,
+    order: &[usize],
+) -> C {
+    let mut entries: Vec<_> = entries.into_iter().map(Some).collect();
+    order
+        .iter()
+        .map(|&index| entries[index].take().expect("entry moved twice"))
+        .collect()
+}
+
+/// Where the `moved` entries start once dropped on `target`, which is not one of them: they take
+/// the place of the target, going after it when dragged down
+fn drop_position(moved: &[usize], target: usize) -> usize {
+    let before = (0..target).filter(|index| !moved.contains(index)).count();
+    if target > moved[0] {
+        before + 1
+    } else {
+        before
+    }
+}
+
+/// Order of the entries once the `moved` ones, in increasing order, are moved as a block starting
+/// at `to` (clamped to the end), with the index where the block starts
+fn move_order(len: usize, moved: &[usize], to: usize) -> (Vec<usize>, usize) {
+    let rest: Vec<_> = (0..len).filter(|index| !moved.contains(index)).collect();
+    let to = to.min(rest.len());
+
+    let mut order = rest[..to].to_vec();
+    order.extend_from_slice(moved);
+    order.extend_from_slice(&rest[to..]);
+    (order, to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn move_blocks() {
+        // Down, the block keeps its order
+        assert_eq!(move_order(6, &[0, 1], 3), (vec![2, 3, 4, 0, 1, 5], 3));
+        // Up, the moved entries need not be next to each other
+        assert_eq!(move_order(6, &[2, 4], 0), (vec![2, 4, 0, 1, 3, 5], 0));
+        // Past the end
+        assert_eq!(move_order(4, &[1], 10), (vec![0, 2, 3, 1], 3));
+        // In place
+        assert_eq!(move_order(3, &[1], 1), (vec![0, 1, 2], 1));
+    }
+
+    #[test]
+    fn drop_on_either_side() {
+        // Dragged down, the entry goes after the target
+        let to = drop_position(&[1], 3);
+        assert_eq!(move_order(5, &[1], to).0, vec![0, 2, 3, 1, 4]);
+        // Dragged up, the entry goes before the target
+        let to = drop_position(&[3], 1);
+        assert_eq!(move_order(5, &[3], to).0, vec![0, 3, 1, 2, 4]);
+        // A block dropped on an entry between its entries goes after it
+        let to = drop_position(&[0, 3], 2);
+        assert_eq!(move_order(5, &[0, 3], to).0, vec![1, 2, 0, 3, 4]);
+        // On the last entry and on the first one
+        let to = drop_position(&[0], 4);
+        assert_eq!(move_order(5, &[0], to).0, vec![1, 2, 3, 4, 0]);
+        let to = drop_position(&[4], 0);
+        assert_eq!(move_order(5, &[4], to).0, vec![4, 0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn reorder_entries() {
+        let entries = vec!["a", "b", "c", "d"];
+        let entries: Vec<_> = reordered(entries, &[2, 0, 3, 1]);
+        assert_eq!(entries, vec!["c", "a", "d", "b"]);
     }
 }
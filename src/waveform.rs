@@ -0,0 +1,260 @@
+use crate::{modals::ClipEditorMessage, Message};
+use iced::{
+    canvas::{self, event, Cache, Cursor, Event, Frame, Geometry, Path, Stroke},
+    mouse, Color, HorizontalAlignment, Point, Rectangle, Size, VerticalAlignment,
+};
+use std::time::Duration;
+
+/// Number of bars of the waveform, whatever the length of the music
+pub(crate) const BARS: usize = 1000;
+
+/// Distance in pixels from the edges of the played part at which they can be dragged
+const HANDLE_MARGIN: f32 = 6.;
+
+const BAR_COLOR: Color = Color::from_rgb(
+    0x72 as f32 / 255.0,
+    0x89 as f32 / 255.0,
+    0xDA as f32 / 255.0,
+);
+const WINDOW_COLOR: Color = Color::from_rgba(
+    0x6F as f32 / 255.0,
+    0xFF as f32 / 255.0,
+    0xE9 as f32 / 255.0,
+    0.25,
+);
+const HANDLE_COLOR: Color = Color::from_rgb(
+    0x6F as f32 / 255.0,
+    0xFF as f32 / 255.0,
+    0xE9 as f32 / 255.0,
+);
+const PLAYHEAD_COLOR: Color = Color::from_rgb(1., 0., 0.2);
+
+/// The played part being moved
+struct Drag {
+    /// Where the part was grabbed, in seconds from its start
+    grab: f32,
+    /// Where the button was pressed, in seconds
+    pressed: f32,
+    moved: bool,
+}
+
+/// Waveform of the music of a clip, with the played part highlighted. The part is moved by
+/// dragging it or its edges, clicking elsewhere previews the music from there.
+pub(crate) struct Waveform {
+    clip: String,
+    /// `None` while the music is decoded, empty when it could not be
+    peaks: Option<Vec<f32>>,
+    bars: Cache,
+
+    /// Length of the music, in seconds
+    total: f32,
+    offset: f32,
+    /// Length of the played part
+    window: f32,
+    pub(crate) playhead: Option<f32>,
+    drag: Option<Drag>,
+}
+
+impl Waveform {
+    pub(crate) fn new(clip: String) -> Self {
+        Self {
+            clip,
+            peaks: None,
+            bars: Default::default(),
+            total: 0.,
+            offset: 0.,
+            window: 0.,
+            playhead: None,
+            drag: None,
+        }
+    }
+
+    pub(crate) fn set_peaks(&mut self, peaks: Vec<f32>) {
+        self.peaks = Some(peaks);
+        self.bars.clear();
+    }
+
+    /// Follows the clip, which can be changed from elsewhere (undo, or another editor)
+    pub(crate) fn set_window(&mut self, total: Duration, offset: Duration, window: Duration) {
+        self.total = total.as_secs_f32();
+        self.window = window.as_secs_f32().min(self.total);
+        // The offset is the one being dragged until the clip catches up
+        if self.drag.is_none() {
+            self.offset = offset.as_secs_f32();
+        }
+    }
+
+    fn x_of(&self, seconds: f32, bounds: Rectangle) -> f32 {
+        if self.total > 0. {
+            seconds / self.total * bounds.width
+        } else {
+            0.
+        }
+    }
+
+    fn seconds_at(&self, x: f32, bounds: Rectangle) -> f32 {
+        (x / bounds.width * self.total).max(0.).min(self.total)
+    }
+
+    /// Where the part would be grabbed from this position, if it is on the part
+    fn grab_at(&self, x: f32, bounds: Rectangle) -> Option<f32> {
+        let start = self.x_of(self.offset, bounds);
+        let end = self.x_of(self.offset + self.window, bounds);
+        if (x - start).abs() <= HANDLE_MARGIN {
+            Some(0.)
+        } else if (x - end).abs() <= HANDLE_MARGIN {
+            Some(self.window)
+        } else if x > start && x < end {
+            Some(self.seconds_at(x, bounds) - self.offset)
+        } else {
+            None
+        }
+    }
+
+    fn on_handle(&self, x: f32, bounds: Rectangle) -> bool {
+        [self.offset, self.offset + self.window]
+            .iter()
+            .any(|&edge| (x - self.x_of(edge, bounds)).abs() <= HANDLE_MARGIN)
+    }
+}
+
+impl canvas::Program<Message> for Waveform {
+    fn update(
+        &mut self,
+        event: Event,
+        bounds: Rectangle,
+        cursor: Cursor,
+    ) -> (event::Status, Option<Message>) {
+        let position = cursor.position_in(&bounds);
+
+        match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                let position = match position {
+                    Some(position) => position,
+                    None => return (event::Status::Ignored, None),
+                };
+                let seconds = self.seconds_at(position.x, bounds);
+                match self.grab_at(position.x, bounds) {
+                    Some(grab) => {
+                        self.drag = Some(Drag {
+                            grab,
+                            pressed: seconds,
+                            moved: false,
+                        });
+                        (event::Status::Captured, None)
+                    }
+                    None => (
+                        event::Status::Captured,
+                        Some(ClipEditorMessage::Seek(Duration::from_secs_f32(seconds)).into()),
+                    ),
+                }
+            }
+            // The part keeps following the cursor when it leaves the waveform, its start is
+            // rounded to a tenth of a second
+            Event::Mouse(mouse::Event::CursorMoved { position: moved_to }) => {
+                let seconds = self.seconds_at(moved_to.x - bounds.x, bounds);
+                let last = ((self.total - self.window).max(0.) * 10.).floor();
+                let tenths = match &mut self.drag {
+                    Some(drag) => {
+                        drag.moved = true;
+                        ((seconds - drag.grab) * 10.).round().max(0.).min(last)
+                    }
+                    None => return (event::Status::Ignored, None),
+                };
+                self.offset = tenths / 10.;
+                (
+                    event::Status::Captured,
+                    Some(Message::EditClipOffset {
+                        clip: self.clip.clone(),
+                        new_offset: Duration::from_millis(tenths as u64 * 100),
+                    }),
+                )
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                let drag = match self.drag.take() {
+                    Some(drag) => drag,
+                    None => return (event::Status::Ignored, None),
+                };
+                // Clicking on the played part without moving it previews from there
+                let message = if drag.moved {
+                    Message::ClipOffsetReleased
+                } else {
+                    ClipEditorMessage::Seek(Duration::from_secs_f32(drag.pressed)).into()
+                };
+                (event::Status::Captured, Some(message))
+            }
+            _ => (event::Status::Ignored, None),
+        }
+    }
+
+    fn draw(&self, bounds: Rectangle, _cursor: Cursor) -> Vec<Geometry> {
+        let bars = self.bars.draw(bounds.size(), |frame| {
+            let center = frame.center();
+            let text = |content: &str| canvas::Text {
+                content: content.into(),
+                position: center,
+                color: Color::WHITE,
+                horizontal_alignment: HorizontalAlignment::Center,
+                vertical_alignment: VerticalAlignment::Center,
+                ..Default::default()
+            };
+            match &self.peaks {
+                None => frame.fill_text(text("Reading the music...")),
+                Some(peaks) if peaks.is_empty() => {
+                    frame.fill_text(text("Could not read the music"))
+                }
+                Some(peaks) => {
+                    let width = frame.width() / peaks.len() as f32;
+                    for (index, peak) in peaks.iter().enumerate() {
+                        let height = (peak * frame.height()).max(1.);
+                        frame.fill_rectangle(
+                            Point::new(index as f32 * width, center.y - height / 2.),
+                            Size::new(width.max(1.), height),
+                            BAR_COLOR,
+                        );
+                    }
+                }
+            }
+        });
+
+        let mut overlay = Frame::new(bounds.size());
+        let start = self.x_of(self.offset, bounds);
+        let end = self.x_of(self.offset + self.window, bounds);
+        overlay.fill_rectangle(
+            Point::new(start, 0.),
+            Size::new(end - start, bounds.height),
+            WINDOW_COLOR,
+        );
+        for x in [start, end].iter().copied() {
+            overlay.stroke(
+                &Path::line(Point::new(x, 0.), Point::new(x, bounds.height)),
+                Stroke::default().with_color(HANDLE_COLOR).with_width(3.),
+            );
+        }
+        if let Some(playhead) = self.playhead {
+            let x = self.x_of(playhead, bounds);
+            overlay.stroke(
+                &Path::line(Point::new(x, 0.), Point::new(x, bounds.height)),
+                Stroke::default().with_color(PLAYHEAD_COLOR).with_width(2.),
+            );
+        }
+
+        vec![bars, overlay.into_geometry()]
+    }
+
+    fn mouse_interaction(&self, bounds: Rectangle, cursor: Cursor) -> mouse::Interaction {
+        if self.drag.is_some() {
+            return mouse::Interaction::Grabbing;
+        }
+        match cursor.position_in(&bounds) {
+            Some(position) if self.on_handle(position.x, bounds) => {
+                mouse::Interaction::ResizingHorizontally
+            }
+            Some(position) if self.grab_at(position.x, bounds).is_some() => {
+                mouse::Interaction::Grab
+            }
+            Some(_) => mouse::Interaction::Pointer,
+            None => mouse::Interaction::default(),
+        }
+    }
+}